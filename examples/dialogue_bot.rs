@@ -1,5 +1,5 @@
-use umlsm::vertex::{EntryVertex, ExitVertex, InitialPseudoState, TerminationPseudoState};
-use umlsm::{Action, CurrentStateIs, Guard, ProcessEvent, ProcessResult};
+use umlsm::vertex::{EntryVertex, ExitVertex, TerminationPseudoState};
+use umlsm::{CurrentStateIs, Guard, ProcessEvent, ProcessResult};
 
 // Vertexes
 
@@ -11,19 +11,21 @@ impl ExitVertex for WaitForName {}
 impl EntryVertex for WaitForName {}
 #[derive(Debug)]
 struct WaitForAge {
-    name: Option<String>,
+    name: String,
 }
 impl EntryVertex for WaitForAge {}
-impl ExitVertex for WaitForAge {
-    fn exit(&mut self) {
-        self.name = None;
+impl ExitVertex for WaitForAge {}
+
+fn new_age(_: &mut (), mes: &NewMessage) -> WaitForAge {
+    WaitForAge {
+        name: mes.0.to_string(),
     }
 }
 
 // Guards
 
 struct MesIs<'a>(&'a str);
-impl Guard<NewMessage, String> for MesIs<'_> {
+impl Guard<NewMessage<'_>, String> for MesIs<'_> {
     fn check(&self, input: &NewMessage) -> Result<(), String> {
         match input.0.to_lowercase() == self.0 {
             true => Ok(()),
@@ -40,64 +42,57 @@ fn is_number(mes: &NewMessage) -> Result<(), String> {
 }
 
 // Events
-struct NewMessage(String);
+struct NewMessage<'a>(&'a str);
+umlsm::borrowed_event!(NewMessage<'a>);
 struct Exit;
+struct Help;
 
 // Actions
 fn start() -> String {
     "Hello! I am dialogue bot. Let's start! Say hello to me.".to_string()
 }
-fn hello() -> String {
+fn hello(_: &mut WaitForHello, _: &mut (), _: &NewMessage, _: &mut WaitForName) -> String {
     "Hello! How is your name?".to_string()
 }
-fn name(event: &NewMessage, target: &mut WaitForAge) -> String {
-    target.name = Some(event.0.clone());
-    format!("Oh, your name is {}! How is your age?", event.0)
+fn name(_: &mut WaitForName, _: &mut (), _: &NewMessage, target: &mut WaitForAge) -> String {
+    format!("Oh, your name is {}! How is your age?", target.name)
 }
-fn age(state: &mut WaitForAge, mes: &NewMessage) -> String {
+fn age(state: &mut WaitForAge, _: &mut (), mes: &NewMessage, _: &mut WaitForHello) -> String {
     let age: u32 = mes.0.parse().unwrap();
-    format!(
-        "Oh, your name is {} and age is {}!",
-        state.name.as_ref().unwrap(),
-        age
-    )
+    format!("Oh, your name is {} and age is {}!", state.name, age)
 }
 
-#[derive(Clone)]
-struct ExitAction;
-impl<Source> Action<Source, (), Exit, TerminationPseudoState, String> for ExitAction {
-    fn trigger(
-        &self,
-        _: &mut Source,
-        _: &mut (),
-        _: &Exit,
-        _: &mut TerminationPseudoState,
-    ) -> String {
-        "Bye, Bye!".to_string()
-    }
+fn exit(_: &mut (), _: &Exit, _: &mut TerminationPseudoState) -> String {
+    "Bye, Bye!".to_string()
+}
+fn help(_: &mut (), _: &Help) -> String {
+    "Answer my questions, or say `exit` to leave.".to_string()
 }
 
 fn main() {
     #[rustfmt::skip]
     let mut sm = umlsm::state_machine!(
         state = (), err = String,
-        [WaitForHello, WaitForName, WaitForAge { name: None }],
+        [WaitForHello, WaitForName, new_age => WaitForAge],
+
+        initial => WaitForHello | start;
 
-        InitialPseudoState + ()                          | start       => WaitForHello,
-        WaitForHello       + NewMessage [MesIs("hello")] | hello       => WaitForName,
-        WaitForName        + NewMessage                  | name        => WaitForAge,
-        WaitForAge         + NewMessage [is_number]      | age         => WaitForHello;
+        WaitForHello       + NewMessage<'_> [MesIs("hello")] | hello   => WaitForName,
+        WaitForName        + NewMessage<'_>                  | name    => WaitForAge,
+        WaitForAge         + NewMessage<'_> [is_number]      | age     => WaitForHello;
 
-        forall:             + Exit                        | ExitAction  => TerminationPseudoState;
+        forall:             + Exit                            | exit    => TerminationPseudoState;
+                            + Help                            | help;
     );
-    let mes = sm.process(&()).unwrap();
+    let mes = sm.start().unwrap();
     println!("{}", mes);
     assert!(sm.is::<WaitForHello>());
 
     repl("You > ", |input| {
         let answer = match input.as_str() {
             "exit" => sm.process(&Exit),
-            _ => sm.process(&NewMessage(input)),
+            "help" => sm.process(&Help),
+            _ => sm.process_borrowed(&NewMessage(&input)),
         };
         match answer {
             ProcessResult::Handled(answer) => {
@@ -107,7 +102,7 @@ fn main() {
                     Ok(format!("Bot > {}", answer))
                 }
             }
-            ProcessResult::GuardErr(e) => Ok(format!("Bot > {}", e)),
+            ProcessResult::GuardErr(e) => Ok(format!("Bot > {}", e.error)),
             _ => unreachable!(),
         }
     })
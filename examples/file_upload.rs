@@ -0,0 +1,59 @@
+use umlsm::vertex::{EntryVertex, ExitVertex};
+use umlsm::{CurrentStateIs, ProcessEvent, ProcessOwnedEvent, ProcessResult};
+
+// Vertexes
+
+struct Idle;
+impl EntryVertex for Idle {}
+impl ExitVertex for Idle {}
+struct Receiving(Vec<u8>);
+impl EntryVertex for Receiving {}
+impl ExitVertex for Receiving {}
+
+// Events
+struct Chunk(Vec<u8>);
+struct Finish;
+
+// Actions
+fn begin(_: &mut Idle, _: &mut Vec<Vec<u8>>, chunk: Chunk, target: &mut Receiving) {
+    target.0 = chunk.0;
+}
+fn append(vertex: &mut Receiving, _: &mut Vec<Vec<u8>>, chunk: Chunk) {
+    vertex.0.extend(chunk.0);
+}
+fn finish(source: &mut Receiving, files: &mut Vec<Vec<u8>>, _: &Finish, _: &mut Idle) {
+    files.push(std::mem::take(&mut source.0));
+}
+
+fn main() {
+    #[rustfmt::skip]
+    let mut sm = umlsm::state_machine!(
+        state = Vec::new(), err = (),
+        [Idle, Receiving(Vec::new())],
+
+        initial => Idle;
+
+        Idle      + move Chunk | begin  => Receiving,
+        Receiving + Finish     | finish => Idle;
+
+        loop:
+        Receiving + move Chunk | append;
+    );
+    sm.start().unwrap();
+
+    // Chunks are moved into the vertex, so they must be processed by value.
+    assert!(matches!(
+        sm.process(&Chunk(vec![1, 2])),
+        ProcessResult::OwnedEventRequired
+    ));
+
+    sm.process_owned(Chunk(vec![1, 2])).unwrap();
+    sm.process_owned(Chunk(vec![3])).unwrap();
+    assert!(sm.is::<Receiving>());
+
+    // Other transitions take events processed by value as usual.
+    sm.process_owned(Finish).unwrap();
+    assert!(sm.is::<Idle>());
+    assert_eq!(*sm.state(), [vec![1, 2, 3]]);
+    println!("Received files: {:?}", sm.state());
+}
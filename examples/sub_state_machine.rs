@@ -20,12 +20,13 @@ fn main() {
         state = (), err = (),
         [@Sub inner_sm],
 
-        InitialPseudoState                         + ()       => StateMachineVertex<InnerSmIdx, _, _, _>,
+        initial => StateMachineVertex<InnerSmIdx, _, _, _>;
+
         (StateMachineVertex<InnerSmIdx, _, _, _>)  + MyEvent  => TerminationPseudoState;
     );
     assert!(sm.is::<InitialPseudoState>());
 
-    sm.process(&()).unwrap();
+    sm.start().unwrap();
     assert!(sm.is::<StateMachineVertex<InnerSmIdx, _, _, _>>());
 
     sm.process(&());
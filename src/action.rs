@@ -15,11 +15,12 @@
 //! `StateMachine::process`.
 //!
 //! #### Rules
-//! 1. If you want to create `ForallTransition`, you must create `struct` and specify `Source` as
-//! generic parameter in `impl Action for YourStruct`.
+//! 1. If you want to create `ForallTransition`, use `ForallAction` instead.
 //! 2. When `Guard` allowed to start `ITransition`, `Action` will be called first. Then will be
-//! called `ExitVertex` for `Source` and `EntryVertex` for `Target`.
-//! 3. `Answer` must be the same for all transitions in `StateMachine`.
+//!    called `ExitVertex` for `Source` and `EntryVertex` for `Target`.
+//! 3. `Answer` must be the same for all transitions in `StateMachine`, unless it is declared with
+//!    `answer = Reply`. Then every action returns `Request::Answer` of its event, see the
+//!    `umlsm::process_event` module.
 //!
 //! #### Implementations
 //! `Action` implemented for:
@@ -33,6 +34,7 @@
 //! Simple answer:
 //! ```
 //! use umlsm::*;
+//! use umlsm::vertex::{InitialPseudoState, TerminationPseudoState};
 //!
 //! fn static_answer() -> &'static str {
 //!     "Hello!"
@@ -72,7 +74,7 @@
 //! Simple answer:
 //! ```
 //! use umlsm::*;
-//! use umlsm::vertex::{EntryVertex, ExitVertex};
+//! use umlsm::vertex::{EntryVertex, ExitVertex, InitialPseudoState, TerminationPseudoState};
 //!
 //! struct State(u32);
 //! impl EntryVertex for State {}
@@ -104,22 +106,187 @@
 //! let answer = sm.process(&()).unwrap();
 //! assert_eq!(answer, "loop");
 //!
-//! assert_eq!(sm.get_vertex::<State, _>().0, 3);
+//! assert_eq!(sm.get_vertex::<State, _>().unwrap().0, 3);
 //!
 //! let answer = sm.process(&ExitEvent).unwrap();
 //! assert_eq!(answer, "exit");
 //! ```
 //! For more complicated examples see `examples` directory.
+//!
+//! ### ForallAction
+//! `ForallAction` is an interface for objects which processes `Event`. It is used only when user
+//! define a transition using `forall` keyword in `state_machine!` or
+//! `StateMachine::add_transition_forall`. `ForallTransition` can be started from any vertex, so
+//! `ForallAction` does not take a `Source` vertex.
+//!
+//! Full list of args for `ForallAction`:
+//! - `Ctx` - a global context which stored in `StateMachine`.
+//! - `Event` - the event which user give for `StateMachine::process`.
+//! - `Target` - a state to which transition will be moved.
+//!
+//! Still `ForallAction` must return an `Answer` which will be returned to user as an answer from
+//! `StateMachine::process`.
+//!
+//! #### Rules
+//! The same as above.
+//!
+//! #### Implementations
+//! `ForallAction` implemented for:
+//! - `Fn(&mut Ctx, &Event, &mut Target) -> Answer`
+//!
+//! #### Examples
+//! Simple answer:
+//! ```
+//! use umlsm::*;
+//! use umlsm::vertex::{EntryVertex, ExitVertex, InitialPseudoState, TerminationPseudoState};
+//!
+//! struct State;
+//! impl EntryVertex for State {}
+//! impl ExitVertex for State {}
+//!
+//! struct Exit;
+//!
+//! fn start() -> &'static str { "start" }
+//! fn exit(_: &mut (), _: &Exit, _: &mut TerminationPseudoState) -> &'static str { "exit" }
+//!
+//! let mut sm = umlsm::state_machine!(
+//!     state = (), err = (),
+//!     [State],
+//!
+//!     InitialPseudoState + () | start => State;
+//!     forall: + Exit          | exit  => TerminationPseudoState;
+//! );
+//! sm.process(&()).unwrap();
+//! let answer = sm.process(&Exit).unwrap();
+//! assert_eq!(answer, "exit");
+//! ```
+//!
+//! ### InternalAction
+//! `InternalAction` is an interface for objects which processes `Event` without leaving the current
+//! vertex. It is used only when user define a forall internal transition using `forall` keyword
+//! without target in `state_machine!` or `StateMachine::add_internal_forall`. Neither
+//! `ExitVertex` nor `EntryVertex` are called for internal transitions.
+//!
+//! Full list of args for `InternalAction`:
+//! - `Ctx` - a global context which stored in `StateMachine`.
+//! - `Event` - the event which user give for `StateMachine::process`.
+//!
+//! Still `InternalAction` must return an `Answer` which will be returned to user as an answer from
+//! `StateMachine::process`.
+//!
+//! #### Implementations
+//! `InternalAction` implemented for:
+//! - `Fn(&mut Ctx, &Event) -> Answer`
+//!
+//! #### Examples
+//! Simple answer:
+//! ```
+//! use umlsm::*;
+//! use umlsm::vertex::{EntryVertex, ExitVertex, InitialPseudoState};
+//!
+//! struct State;
+//! impl EntryVertex for State {}
+//! impl ExitVertex for State {}
+//!
+//! struct Help;
+//!
+//! fn start() -> &'static str { "start" }
+//! fn help(_: &mut (), _: &Help) -> &'static str { "help" }
+//!
+//! let mut sm = umlsm::state_machine!(
+//!     state = (), err = (),
+//!     [State],
+//!
+//!     InitialPseudoState + () | start => State;
+//!     forall: + Help          | help;
+//! );
+//! sm.process(&()).unwrap();
+//! let answer = sm.process(&Help).unwrap();
+//! assert_eq!(answer, "help");
+//! assert!(sm.is::<State>());
+//! ```
+//!
+//! ### OwnedAction and OwnedActionLoop
+//! `OwnedAction` and `OwnedActionLoop` are the same as `Action` and `ActionLoop`, but they take the
+//! event by value, so its payload can be moved into a vertex or the context without copying. Such
+//! transitions are written with `move` before the event type in `state_machine!` and are started
+//! only by `ProcessOwnedEvent::process_owned`. If such a transition accepts an event processed by
+//! reference, `ProcessResult::OwnedEventRequired` is returned. Other transitions receive a reference
+//! to the event processed by value as usual.
+//!
+//! #### Implementations
+//! `OwnedAction` implemented for:
+//! - `Fn(&mut Source, &mut Ctx, Event, &mut Target) -> Answer`
+//!
+//! `OwnedActionLoop` implemented for:
+//! - `Fn(&mut Vertex, &mut Ctx, Event) -> Answer`
+//!
+//! #### Examples
+//! ```
+//! use umlsm::*;
+//! use umlsm::vertex::{EntryVertex, ExitVertex, InitialPseudoState};
+//!
+//! struct Empty;
+//! impl EntryVertex for Empty {}
+//! impl ExitVertex for Empty {}
+//! struct Loaded(Vec<u8>);
+//! impl EntryVertex for Loaded {}
+//! impl ExitVertex for Loaded {}
+//!
+//! struct File(Vec<u8>);
+//!
+//! fn load(_: &mut Empty, _: &mut (), file: File, target: &mut Loaded) {
+//!     target.0 = file.0;
+//! }
+//! fn append(vertex: &mut Loaded, _: &mut (), file: File) {
+//!     vertex.0.extend(file.0);
+//! }
+//!
+//! let mut sm = umlsm::state_machine!(
+//!     state = (), err = (),
+//!     [Empty, Loaded(Vec::new())],
+//!
+//!     InitialPseudoState + ()        => Empty,
+//!     Empty              + move File | load => Loaded;
+//!     loop: Loaded       + move File | append;
+//! );
+//! sm.process(&()).unwrap();
+//! assert!(matches!(
+//!     sm.process(&File(vec![1, 2])),
+//!     ProcessResult::OwnedEventRequired
+//! ));
+//!
+//! sm.process_owned(File(vec![1, 2])).unwrap();
+//! sm.process_owned(File(vec![3])).unwrap();
+//! assert_eq!(sm.get_vertex::<Loaded, _>().unwrap().0, [1, 2, 3]);
+//! ```
 
+#[allow(clippy::module_inception)]
 mod action;
 mod action_loop;
 mod action_loop_wrappers;
+mod forall_action;
+mod internal_action;
+mod owned_action;
+mod owned_action_loop;
+mod prioritized;
+mod reply;
 mod wrappers;
 
-pub use {action::Action, action_loop::ActionLoop};
+pub use {
+    action::Action,
+    action_loop::ActionLoop,
+    forall_action::ForallAction,
+    internal_action::InternalAction,
+    owned_action::OwnedAction,
+    owned_action_loop::OwnedActionLoop,
+    prioritized::Prioritized,
+    reply::{Answered, AnswersTo, EventRefs, Reply, SameAnswer},
+};
 
 #[doc(hidden)]
 pub use {
-    action_loop_wrappers::{EmptyActionLoop, EmptyForallAction},
+    action_loop_wrappers::{EmptyActionLoop, EmptyForallAction, EmptyInternalAction},
+    reply::Replying,
     wrappers::{EmptyAction, FnIntoStruct},
 };
@@ -1,7 +1,7 @@
 /// An interface for actions that must be called when running `ITransition`.
 ///
 /// See module-level documentation for more information.
-pub trait Action<Source, Ctx, Event, Target, Answer> {
+pub trait Action<Source, Ctx, Event: ?Sized, Target, Answer> {
     fn trigger(
         &self,
         source: &mut Source,
@@ -9,9 +9,19 @@ pub trait Action<Source, Ctx, Event, Target, Answer> {
         event: &Event,
         target: &mut Target,
     ) -> Answer;
+
+    /// The name of the action for `MachineDescription`, or `None` for an empty action.
+    fn name(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<Self>())
+    }
+
+    /// The priority of the transition with this action, `0` by default. Set by `Prioritized`.
+    fn priority(&self) -> i32 {
+        0
+    }
 }
 
-impl<Source, Ctx, Event, Target, F, Answer> Action<Source, Ctx, Event, Target, Answer> for F
+impl<Source, Ctx, Event: ?Sized, Target, F, Answer> Action<Source, Ctx, Event, Target, Answer> for F
 where
     F: Fn(&mut Source, &mut Ctx, &Event, &mut Target) -> Answer,
 {
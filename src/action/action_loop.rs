@@ -7,11 +7,21 @@
 /// `Action` trait can require both `Source` and `Target` transitions at the same time by the
 /// mutable reference, and when `Source` == `Target` in case of loops, we got 2 mutable references for
 /// the one address of memory, which is UB (undefined behaviour).
-pub trait ActionLoop<Source, Ctx, Event, Answer> {
+pub trait ActionLoop<Source, Ctx, Event: ?Sized, Answer> {
     fn trigger(&self, source: &mut Source, ctx: &mut Ctx, event: &Event) -> Answer;
+
+    /// The name of the action for `MachineDescription`, or `None` for an empty action.
+    fn name(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<Self>())
+    }
+
+    /// The priority of the transition with this action, `0` by default. Set by `Prioritized`.
+    fn priority(&self) -> i32 {
+        0
+    }
 }
 
-impl<Source, Ctx, Event, F, Answer> ActionLoop<Source, Ctx, Event, Answer> for F
+impl<Source, Ctx, Event: ?Sized, F, Answer> ActionLoop<Source, Ctx, Event, Answer> for F
 where
     F: Fn(&mut Source, &mut Ctx, &Event) -> Answer,
 {
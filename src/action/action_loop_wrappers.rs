@@ -1,30 +1,90 @@
-use crate::action::ActionLoop;
+use crate::action::{ActionLoop, ForallAction, InternalAction};
 use std::marker::PhantomData;
 
 pub struct EmptyActionLoop<Source, Event>(PhantomData<(Source, Event)>);
 
+impl<Source, Event> Clone for EmptyActionLoop<Source, Event> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
 impl<Source, Event> EmptyActionLoop<Source, Event> {
     pub fn new() -> Self {
         EmptyActionLoop(PhantomData)
     }
 }
 
-impl<Source, Ctx, Event> ActionLoop<Source, Ctx, Event, ()> for EmptyActionLoop<Source, Event> {
-    fn trigger(&self, _: &mut Source, _: &mut Ctx, _: &Event) -> () {
-        ()
+impl<Source, Event> Default for EmptyActionLoop<Source, Event> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Source, Ctx, Event: ?Sized, Trig> ActionLoop<Source, Ctx, Event, ()>
+    for EmptyActionLoop<Source, Trig>
+{
+    fn trigger(&self, _: &mut Source, _: &mut Ctx, _: &Event) {}
+
+    fn name(&self) -> Option<&'static str> {
+        None
     }
 }
 
 pub struct EmptyForallAction<Event>(PhantomData<Event>);
 
+impl<Event> Clone for EmptyForallAction<Event> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
 impl<Event> EmptyForallAction<Event> {
     pub fn new() -> Self {
         EmptyForallAction(PhantomData)
     }
 }
 
-impl<Source, Ctx, Event> ActionLoop<Source, Ctx, Event, ()> for EmptyForallAction<Event> {
-    fn trigger(&self, _: &mut Source, _: &mut Ctx, _: &Event) -> () {
-        ()
+impl<Event> Default for EmptyForallAction<Event> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Ctx, Event: ?Sized, Trig, Target> ForallAction<Ctx, Event, Target, ()>
+    for EmptyForallAction<Trig>
+{
+    fn trigger(&self, _: &mut Ctx, _: &Event, _: &mut Target) {}
+
+    fn name(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+pub struct EmptyInternalAction<Event>(PhantomData<Event>);
+
+impl<Event> Clone for EmptyInternalAction<Event> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl<Event> EmptyInternalAction<Event> {
+    pub fn new() -> Self {
+        EmptyInternalAction(PhantomData)
+    }
+}
+
+impl<Event> Default for EmptyInternalAction<Event> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Ctx, Event: ?Sized, Trig> InternalAction<Ctx, Event, ()> for EmptyInternalAction<Trig> {
+    fn trigger(&self, _: &mut Ctx, _: &Event) {}
+
+    fn name(&self) -> Option<&'static str> {
+        None
     }
 }
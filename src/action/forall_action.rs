@@ -0,0 +1,31 @@
+/// An interface for actions that must be called when running `ForallTransition`.
+///
+/// See module-level documentation for more information.
+///
+/// ### Why we need just one more trait?
+///
+/// `ForallTransition` can be started from any vertex of `StateMachine`, so `Action` for it must be
+/// implemented for every `Source`. Closures cannot be generic, so `ForallAction` does not take a
+/// `Source` at all.
+pub trait ForallAction<Ctx, Event: ?Sized, Target, Answer> {
+    fn trigger(&self, ctx: &mut Ctx, event: &Event, target: &mut Target) -> Answer;
+
+    /// The name of the action for `MachineDescription`, or `None` for an empty action.
+    fn name(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<Self>())
+    }
+
+    /// The priority of the transition with this action, `0` by default. Set by `Prioritized`.
+    fn priority(&self) -> i32 {
+        0
+    }
+}
+
+impl<Ctx, Event: ?Sized, Target, F, Answer> ForallAction<Ctx, Event, Target, Answer> for F
+where
+    F: Fn(&mut Ctx, &Event, &mut Target) -> Answer,
+{
+    fn trigger(&self, ctx: &mut Ctx, event: &Event, target: &mut Target) -> Answer {
+        self(ctx, event, target)
+    }
+}
@@ -0,0 +1,30 @@
+/// An interface for actions that must be called when running `ForallInternalTransition`.
+///
+/// See module-level documentation for more information.
+///
+/// ### Why we need just one more trait?
+///
+/// Internal transition does not leave the current vertex, so there are no `Target` vertex, and it
+/// can be started from any vertex, so there are no `Source` vertex.
+pub trait InternalAction<Ctx, Event: ?Sized, Answer> {
+    fn trigger(&self, ctx: &mut Ctx, event: &Event) -> Answer;
+
+    /// The name of the action for `MachineDescription`, or `None` for an empty action.
+    fn name(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<Self>())
+    }
+
+    /// The priority of the transition with this action, `0` by default. Set by `Prioritized`.
+    fn priority(&self) -> i32 {
+        0
+    }
+}
+
+impl<Ctx, Event: ?Sized, F, Answer> InternalAction<Ctx, Event, Answer> for F
+where
+    F: Fn(&mut Ctx, &Event) -> Answer,
+{
+    fn trigger(&self, ctx: &mut Ctx, event: &Event) -> Answer {
+        self(ctx, event)
+    }
+}
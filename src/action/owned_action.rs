@@ -0,0 +1,38 @@
+/// An interface for actions that take the event by value. They are called only when the event is
+/// processed by `StateMachine::process_owned`.
+///
+/// See module-level documentation for more information.
+pub trait OwnedAction<Source, Ctx, Event, Target, Answer> {
+    fn trigger(
+        &self,
+        source: &mut Source,
+        ctx: &mut Ctx,
+        event: Event,
+        target: &mut Target,
+    ) -> Answer;
+
+    /// The name of the action for `MachineDescription`, or `None` for an empty action.
+    fn name(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<Self>())
+    }
+
+    /// The priority of the transition with this action, `0` by default. Set by `Prioritized`.
+    fn priority(&self) -> i32 {
+        0
+    }
+}
+
+impl<Source, Ctx, Event, Target, F, Answer> OwnedAction<Source, Ctx, Event, Target, Answer> for F
+where
+    F: Fn(&mut Source, &mut Ctx, Event, &mut Target) -> Answer,
+{
+    fn trigger(
+        &self,
+        source: &mut Source,
+        ctx: &mut Ctx,
+        event: Event,
+        target: &mut Target,
+    ) -> Answer {
+        self(source, ctx, event, target)
+    }
+}
@@ -0,0 +1,26 @@
+/// An interface for loop actions that take the event by value. They are called only when the event
+/// is processed by `StateMachine::process_owned`.
+///
+/// See module-level documentation for more information.
+pub trait OwnedActionLoop<Source, Ctx, Event, Answer> {
+    fn trigger(&self, source: &mut Source, ctx: &mut Ctx, event: Event) -> Answer;
+
+    /// The name of the action for `MachineDescription`, or `None` for an empty action.
+    fn name(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<Self>())
+    }
+
+    /// The priority of the transition with this action, `0` by default. Set by `Prioritized`.
+    fn priority(&self) -> i32 {
+        0
+    }
+}
+
+impl<Source, Ctx, Event, F, Answer> OwnedActionLoop<Source, Ctx, Event, Answer> for F
+where
+    F: Fn(&mut Source, &mut Ctx, Event) -> Answer,
+{
+    fn trigger(&self, source: &mut Source, ctx: &mut Ctx, event: Event) -> Answer {
+        self(source, ctx, event)
+    }
+}
@@ -0,0 +1,151 @@
+use crate::action::{
+    Action, ActionLoop, FnIntoStruct, ForallAction, InternalAction, OwnedAction, OwnedActionLoop,
+};
+
+/// Wraps an action and sets the priority of its transition. Transitions with a higher priority win
+/// over other enabled transitions, see `umlsm::transition` module.
+///
+/// `state_machine!` wraps the action of a line written after `priority(N)` into it.
+pub struct Prioritized<A> {
+    priority: i32,
+    action: A,
+}
+
+impl<A> Prioritized<A> {
+    pub fn new(priority: i32, action: A) -> Self {
+        Prioritized { priority, action }
+    }
+}
+
+impl<A: Clone> Clone for Prioritized<A> {
+    fn clone(&self) -> Self {
+        Prioritized::new(self.priority, self.action.clone())
+    }
+}
+
+impl<F, A> FnIntoStruct<Prioritized<A>> for Prioritized<F>
+where
+    F: FnIntoStruct<A>,
+{
+    fn into(self) -> Prioritized<A> {
+        Prioritized::new(self.priority, self.action.into())
+    }
+}
+
+impl<Source, Ctx, Event: ?Sized, Target, A, Answer> Action<Source, Ctx, Event, Target, Answer>
+    for Prioritized<A>
+where
+    A: Action<Source, Ctx, Event, Target, Answer>,
+{
+    fn trigger(
+        &self,
+        source: &mut Source,
+        ctx: &mut Ctx,
+        event: &Event,
+        target: &mut Target,
+    ) -> Answer {
+        self.action.trigger(source, ctx, event, target)
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.action.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+}
+
+impl<Source, Ctx, Event: ?Sized, A, Answer> ActionLoop<Source, Ctx, Event, Answer>
+    for Prioritized<A>
+where
+    A: ActionLoop<Source, Ctx, Event, Answer>,
+{
+    fn trigger(&self, source: &mut Source, ctx: &mut Ctx, event: &Event) -> Answer {
+        self.action.trigger(source, ctx, event)
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.action.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+}
+
+impl<Ctx, Event: ?Sized, Target, A, Answer> ForallAction<Ctx, Event, Target, Answer>
+    for Prioritized<A>
+where
+    A: ForallAction<Ctx, Event, Target, Answer>,
+{
+    fn trigger(&self, ctx: &mut Ctx, event: &Event, target: &mut Target) -> Answer {
+        self.action.trigger(ctx, event, target)
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.action.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+}
+
+impl<Ctx, Event: ?Sized, A, Answer> InternalAction<Ctx, Event, Answer> for Prioritized<A>
+where
+    A: InternalAction<Ctx, Event, Answer>,
+{
+    fn trigger(&self, ctx: &mut Ctx, event: &Event) -> Answer {
+        self.action.trigger(ctx, event)
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.action.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+}
+
+impl<Source, Ctx, Event, Target, A, Answer> OwnedAction<Source, Ctx, Event, Target, Answer>
+    for Prioritized<A>
+where
+    A: OwnedAction<Source, Ctx, Event, Target, Answer>,
+{
+    fn trigger(
+        &self,
+        source: &mut Source,
+        ctx: &mut Ctx,
+        event: Event,
+        target: &mut Target,
+    ) -> Answer {
+        self.action.trigger(source, ctx, event, target)
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.action.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+}
+
+impl<Source, Ctx, Event, A, Answer> OwnedActionLoop<Source, Ctx, Event, Answer> for Prioritized<A>
+where
+    A: OwnedActionLoop<Source, Ctx, Event, Answer>,
+{
+    fn trigger(&self, source: &mut Source, ctx: &mut Ctx, event: Event) -> Answer {
+        self.action.trigger(source, ctx, event)
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.action.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+}
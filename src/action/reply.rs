@@ -0,0 +1,273 @@
+use crate::action::{
+    Action, ActionLoop, FnIntoStruct, ForallAction, InternalAction, OwnedAction, OwnedActionLoop,
+};
+use crate::trigger::AnyOf;
+use crate::Request;
+use frunk::coproduct::CNil;
+use frunk::{Coproduct, HCons, HNil};
+use std::any::{type_name, Any};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// The `Answer` of a state machine declared with `answer = Reply`. Every transition of such a
+/// state machine may return its own answer type, which is boxed into `Reply`.
+///
+/// Every action of such a state machine returns `Request::Answer` of its event, which is checked at
+/// compile time, so `ProcessRequest::request` gets the answer back with the type of
+/// `Request::Answer`.
+pub struct Reply {
+    answer: Box<dyn Any>,
+    type_name: &'static str,
+}
+
+impl Reply {
+    pub(crate) fn new<R: 'static>(answer: R) -> Self {
+        Reply {
+            answer: Box::new(answer),
+            type_name: type_name::<R>(),
+        }
+    }
+
+    /// Returns the type name of the answer.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the answer to the request `E`. `Replying` is the only way to make a `Reply`, and it
+    /// makes the answer of a transition triggered by `E` from `E::Answer`, which is checked by
+    /// `AnswersTo`.
+    pub(crate) fn into_answer<E: Request>(self) -> E::Answer {
+        match self.answer.downcast() {
+            Ok(answer) => *answer,
+            Err(_) => unreachable!("`Replying` makes answers to `E` only from `E::Answer`"),
+        }
+    }
+
+    /// Returns the answer if it has the type `R`, or the same `Reply` otherwise.
+    pub fn downcast<R: 'static>(self) -> Result<R, Self> {
+        let type_name = self.type_name;
+        match self.answer.downcast() {
+            Ok(answer) => Ok(*answer),
+            Err(answer) => Err(Reply { answer, type_name }),
+        }
+    }
+}
+
+impl fmt::Debug for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Reply({})", self.type_name)
+    }
+}
+
+/// The answer type that actions of a transition return in a state machine declared with
+/// `answer = Reply`.
+///
+/// It is `Request::Answer` of the event, or the common answer of all events of `AnyOf`. Other
+/// triggers, such as families, borrowed events and catch-all transitions, have no single answer
+/// type, so they cannot be used in such a state machine.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not have a `Request::Answer`",
+    note = "every event of a state machine declared with `answer = Reply` must implement `Request`"
+)]
+pub trait Answered {
+    type Answer: 'static;
+}
+
+impl<E: Request> Answered for E {
+    type Answer = E::Answer;
+}
+
+impl<L: SameAnswer> Answered for AnyOf<L> {
+    type Answer = L::Answer;
+}
+
+/// A list of requests with the same answer.
+#[diagnostic::on_unimplemented(
+    message = "events of `{Self}` must implement `Request` with the same `Answer`"
+)]
+pub trait SameAnswer {
+    type Answer: 'static;
+}
+
+impl<E: Request> SameAnswer for HCons<E, HNil> {
+    type Answer = E::Answer;
+}
+
+impl<E, F, Rest> SameAnswer for HCons<E, HCons<F, Rest>>
+where
+    E: Request,
+    HCons<F, Rest>: SameAnswer<Answer = E::Answer>,
+{
+    type Answer = E::Answer;
+}
+
+/// Ties `Answered` to the event that an action receives: `E` itself for a single event, or
+/// `Coprod!(&A, &B, ...)` for `AnyOf<Hlist![A, B, ...]>`. So `Replying` made for one event cannot
+/// wrap an action of a transition triggered by another event.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not the trigger of a transition whose action receives `{Event}`",
+    note = "`Replying` must be made for the event of its transition"
+)]
+pub trait AnswersTo<Event: ?Sized>: Answered {}
+
+impl<E: Request> AnswersTo<E> for E {}
+
+impl<L: SameAnswer + EventRefs<Refs>, Refs> AnswersTo<Refs> for AnyOf<L> {}
+
+/// A list of events whose references are `Refs`, such as `Coprod!(&'a A, &'a B)` for
+/// `Hlist![A, B]`.
+pub trait EventRefs<Refs> {}
+
+impl EventRefs<CNil> for HNil {}
+
+impl<E, Rest, RefsRest> EventRefs<Coproduct<&E, RefsRest>> for HCons<E, Rest> where
+    Rest: EventRefs<RefsRest>
+{
+}
+
+/// Wraps an action of a transition triggered by `E` into an action that returns `Reply`. The
+/// action must return `Answered::Answer` of `E`, and receive the event of `E` by `AnswersTo`.
+pub struct Replying<A, E>(A, PhantomData<fn(&E)>);
+
+impl<A, E> Replying<A, E> {
+    pub fn new(action: A) -> Self {
+        Replying(action, PhantomData)
+    }
+}
+
+impl<A: Clone, E> Clone for Replying<A, E> {
+    fn clone(&self) -> Self {
+        Replying::new(self.0.clone())
+    }
+}
+
+impl<F, A, E> FnIntoStruct<Replying<A, E>> for Replying<F, E>
+where
+    F: FnIntoStruct<A>,
+{
+    fn into(self) -> Replying<A, E> {
+        Replying::new(self.0.into())
+    }
+}
+
+impl<Source, Ctx, Event: ?Sized, Target, A, E> Action<Source, Ctx, Event, Target, Reply>
+    for Replying<A, E>
+where
+    A: Action<Source, Ctx, Event, Target, E::Answer>,
+    E: AnswersTo<Event>,
+{
+    fn trigger(
+        &self,
+        source: &mut Source,
+        ctx: &mut Ctx,
+        event: &Event,
+        target: &mut Target,
+    ) -> Reply {
+        Reply::new(self.0.trigger(source, ctx, event, target))
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.0.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.0.priority()
+    }
+}
+
+impl<Source, Ctx, Event: ?Sized, A, E> ActionLoop<Source, Ctx, Event, Reply> for Replying<A, E>
+where
+    A: ActionLoop<Source, Ctx, Event, E::Answer>,
+    E: AnswersTo<Event>,
+{
+    fn trigger(&self, source: &mut Source, ctx: &mut Ctx, event: &Event) -> Reply {
+        Reply::new(self.0.trigger(source, ctx, event))
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.0.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.0.priority()
+    }
+}
+
+impl<Ctx, Event: ?Sized, Target, A, E> ForallAction<Ctx, Event, Target, Reply> for Replying<A, E>
+where
+    A: ForallAction<Ctx, Event, Target, E::Answer>,
+    E: AnswersTo<Event>,
+{
+    fn trigger(&self, ctx: &mut Ctx, event: &Event, target: &mut Target) -> Reply {
+        Reply::new(self.0.trigger(ctx, event, target))
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.0.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.0.priority()
+    }
+}
+
+impl<Ctx, Event: ?Sized, A, E> InternalAction<Ctx, Event, Reply> for Replying<A, E>
+where
+    A: InternalAction<Ctx, Event, E::Answer>,
+    E: AnswersTo<Event>,
+{
+    fn trigger(&self, ctx: &mut Ctx, event: &Event) -> Reply {
+        Reply::new(self.0.trigger(ctx, event))
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.0.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.0.priority()
+    }
+}
+
+impl<Source, Ctx, Event, Target, A, E> OwnedAction<Source, Ctx, Event, Target, Reply>
+    for Replying<A, E>
+where
+    A: OwnedAction<Source, Ctx, Event, Target, E::Answer>,
+    E: AnswersTo<Event>,
+{
+    fn trigger(
+        &self,
+        source: &mut Source,
+        ctx: &mut Ctx,
+        event: Event,
+        target: &mut Target,
+    ) -> Reply {
+        Reply::new(self.0.trigger(source, ctx, event, target))
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.0.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.0.priority()
+    }
+}
+
+impl<Source, Ctx, Event, A, E> OwnedActionLoop<Source, Ctx, Event, Reply> for Replying<A, E>
+where
+    A: OwnedActionLoop<Source, Ctx, Event, E::Answer>,
+    E: AnswersTo<Event>,
+{
+    fn trigger(&self, source: &mut Source, ctx: &mut Ctx, event: Event) -> Reply {
+        Reply::new(self.0.trigger(source, ctx, event))
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.0.name()
+    }
+
+    fn priority(&self) -> i32 {
+        self.0.priority()
+    }
+}
@@ -1,25 +1,40 @@
 use crate::action::action::Action;
+use std::any::type_name;
 use std::marker::PhantomData;
 
 pub struct EmptyAction<Source, Event>(PhantomData<(Source, Event)>);
 
+impl<Source, Event> Clone for EmptyAction<Source, Event> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
 impl<Source, Event> EmptyAction<Source, Event> {
     pub fn new() -> Self {
         EmptyAction(PhantomData)
     }
 }
 
+impl<Source, Event> Default for EmptyAction<Source, Event> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<Source, Event> FnIntoStruct<EmptyAction<Source, Event>> for EmptyAction<Source, Event> {
     fn into(self) -> EmptyAction<Source, Event> {
         self
     }
 }
 
-impl<Source, Ctx, Event, Target> Action<Source, Ctx, Event, Target, ()>
-    for EmptyAction<Source, Event>
+impl<Source, Ctx, Event: ?Sized, Trig, Target> Action<Source, Ctx, Event, Target, ()>
+    for EmptyAction<Source, Trig>
 {
-    fn trigger(&self, _: &mut Source, _: &mut Ctx, _: &Event, _: &mut Target) -> () {
-        ()
+    fn trigger(&self, _: &mut Source, _: &mut Ctx, _: &Event, _: &mut Target) {}
+
+    fn name(&self) -> Option<&'static str> {
+        None
     }
 }
 
@@ -29,6 +44,12 @@ pub trait FnIntoStruct<T> {
 
 pub struct StaticOutput<F, Answer>(F, PhantomData<Answer>);
 
+impl<F: Clone, Answer> Clone for StaticOutput<F, Answer> {
+    fn clone(&self) -> Self {
+        StaticOutput(self.0.clone(), PhantomData)
+    }
+}
+
 impl<F, Answer> StaticOutput<F, Answer>
 where
     F: Fn() -> Answer,
@@ -45,6 +66,10 @@ where
     fn trigger(&self, _: &mut Source, _: &mut Ctx, _: &Event, _: &mut Target) -> Answer {
         (self.0)()
     }
+
+    fn name(&self) -> Option<&'static str> {
+        Some(type_name::<F>())
+    }
 }
 impl<F, Answer> FnIntoStruct<StaticOutput<F, Answer>> for F
 where
@@ -56,6 +81,12 @@ where
 }
 
 pub struct FuncActionAllArgs<F, Other>(F, PhantomData<Other>);
+
+impl<F: Clone, Other> Clone for FuncActionAllArgs<F, Other> {
+    fn clone(&self) -> Self {
+        FuncActionAllArgs(self.0.clone(), PhantomData)
+    }
+}
 impl<Source, Ctx, Event, Target, F, Answer> Action<Source, Ctx, Event, Target, Answer>
     for FuncActionAllArgs<F, (Source, Ctx, Event, Target, Answer)>
 where
@@ -70,6 +101,10 @@ where
     ) -> Answer {
         (self.0)(source, ctx, event, target)
     }
+
+    fn name(&self) -> Option<&'static str> {
+        Some(type_name::<F>())
+    }
 }
 
 impl<F, Source, Ctx, Event, Target, Answer>
@@ -83,6 +118,12 @@ where
 }
 
 pub struct FuncActionSourceEvent<F, Other>(F, PhantomData<Other>);
+
+impl<F: Clone, Other> Clone for FuncActionSourceEvent<F, Other> {
+    fn clone(&self) -> Self {
+        FuncActionSourceEvent(self.0.clone(), PhantomData)
+    }
+}
 impl<Source, Ctx, Event, Target, F, Answer> Action<Source, Ctx, Event, Target, Answer>
     for FuncActionSourceEvent<F, (Source, Event, Answer)>
 where
@@ -91,6 +132,10 @@ where
     fn trigger(&self, source: &mut Source, _: &mut Ctx, event: &Event, _: &mut Target) -> Answer {
         (self.0)(source, event)
     }
+
+    fn name(&self) -> Option<&'static str> {
+        Some(type_name::<F>())
+    }
 }
 
 impl<F, Source, Event, Answer> FnIntoStruct<FuncActionSourceEvent<F, (Source, Event, Answer)>> for F
@@ -103,6 +148,12 @@ where
 }
 
 pub struct FuncActionEventTarget<F, Other>(F, PhantomData<Other>);
+
+impl<F: Clone, Other> Clone for FuncActionEventTarget<F, Other> {
+    fn clone(&self) -> Self {
+        FuncActionEventTarget(self.0.clone(), PhantomData)
+    }
+}
 impl<Source, Ctx, Event, Target, F, Answer> Action<Source, Ctx, Event, Target, Answer>
     for FuncActionEventTarget<F, (Event, Target, Answer)>
 where
@@ -111,6 +162,10 @@ where
     fn trigger(&self, _: &mut Source, _: &mut Ctx, event: &Event, target: &mut Target) -> Answer {
         (self.0)(event, target)
     }
+
+    fn name(&self) -> Option<&'static str> {
+        Some(type_name::<F>())
+    }
 }
 
 impl<F, Event, Target, Answer> FnIntoStruct<FuncActionEventTarget<F, (Event, Target, Answer)>> for F
@@ -123,6 +178,12 @@ where
 }
 
 pub struct FuncActionSourceEventTarget<F, Other>(F, PhantomData<Other>);
+
+impl<F: Clone, Other> Clone for FuncActionSourceEventTarget<F, Other> {
+    fn clone(&self) -> Self {
+        FuncActionSourceEventTarget(self.0.clone(), PhantomData)
+    }
+}
 impl<Source, Ctx, Event, Target, F, Answer> Action<Source, Ctx, Event, Target, Answer>
     for FuncActionSourceEventTarget<F, (Source, Event, Target, Answer)>
 where
@@ -137,6 +198,10 @@ where
     ) -> Answer {
         (self.0)(source, event, target)
     }
+
+    fn name(&self) -> Option<&'static str> {
+        Some(type_name::<F>())
+    }
 }
 
 impl<F, Source, Event, Target, Answer>
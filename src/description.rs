@@ -0,0 +1,397 @@
+//! Runtime descriptions of the structure of `StateMachine`.
+//!
+//! Vertices and transitions exist only as types of `HList`s, so they are described by walking the
+//! lists. Triggers are described by type names of their events, see `Trigger::event_names`,
+//! actions by `Action::name` and guards by `Guard::count`.
+//!
+//! Example:
+//! ```
+//! use umlsm::description::VertexDescription;
+//! use umlsm::process_result::TransitionKind;
+//! use umlsm::vertex::{EntryVertex, ExitVertex, InitialPseudoState};
+//!
+//! struct Locked;
+//! impl EntryVertex for Locked {}
+//! impl ExitVertex for Locked {}
+//! struct Unlocked;
+//! impl EntryVertex for Unlocked {}
+//! impl ExitVertex for Unlocked {}
+//!
+//! struct Coin;
+//! struct Push;
+//!
+//! fn paid(coin: &Coin) -> Result<(), ()> { Ok(()) }
+//! fn count(_: &mut Unlocked, passes: &mut u32, _: &Push, _: &mut Locked) { *passes += 1; }
+//!
+//! let sm = umlsm::state_machine!(
+//!     state = 0, err = (),
+//!     [Locked, Unlocked],
+//!
+//!     InitialPseudoState + ()            => Locked,
+//!     Locked   + Coin [paid]             => Unlocked,
+//!     Unlocked + Push | count            => Locked;
+//! );
+//!
+//! let description = sm.describe();
+//! assert!(description.vertex(std::any::type_name::<Unlocked>()).is_some());
+//!
+//! let unlock = description.transitions_from(std::any::type_name::<Locked>()).next().unwrap();
+//! assert_eq!(unlock.events, vec![std::any::type_name::<Coin>()]);
+//! assert_eq!(unlock.target, Some(std::any::type_name::<Unlocked>()));
+//! assert_eq!(unlock.kind, TransitionKind::Normal);
+//! assert_eq!(unlock.guards, 1);
+//! assert_eq!(unlock.action, None);
+//!
+//! let pass = description.transitions_from(std::any::type_name::<Unlocked>()).next().unwrap();
+//! assert_eq!(pass.guards, 0);
+//! assert!(pass.action.unwrap().ends_with("count"));
+//! ```
+
+use crate::action::{
+    Action, ActionLoop, ForallAction, InternalAction, OwnedAction, OwnedActionLoop,
+};
+use crate::guard::Guard;
+use crate::hmap::{HMap, HMapNil};
+use crate::process_result::TransitionKind;
+use crate::transition::{
+    CatchAllTransition, DeferTransition, ForallInternalTransition, ForallTransition,
+    IgnoreTransition, LoopTransition, OwnedLoopTransition, OwnedTransition, Transition,
+};
+use crate::trigger::Trigger;
+use crate::utils::TypeList;
+use crate::vert_handler::{EmptyVertexHandler, SubStateMachineVertexHandler};
+use crate::vertex::{StateMachineVertex, VertexSlot};
+use crate::StateMachine;
+use frunk::{HCons, HNil};
+use std::any::type_name;
+use std::marker::PhantomData;
+
+/// Description of the structure of a state machine, see `StateMachine::describe`.
+///
+/// The order of vertices and transitions is not specified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MachineDescription {
+    pub vertices: Vec<VertexDescription>,
+    pub transitions: Vec<TransitionDescription>,
+}
+
+impl MachineDescription {
+    /// Find the vertex with the type name `name`.
+    pub fn vertex(&self, name: &str) -> Option<&VertexDescription> {
+        self.vertices.iter().find(|vertex| vertex.name == name)
+    }
+
+    /// Transitions that can be started from the vertex with the type name `vertex`, including
+    /// forall ones.
+    pub fn transitions_from<'a>(
+        &'a self,
+        vertex: &'a str,
+    ) -> impl Iterator<Item = &'a TransitionDescription> + 'a {
+        self.transitions
+            .iter()
+            .filter(move |transition| transition.starts_from(vertex))
+    }
+}
+
+/// Description of one vertex.
+///
+/// - `name` - the type name of the vertex.
+/// - `sub_machine` - the description of the sub state machine, if the vertex is a
+///   `StateMachineVertex`. It is `None` also when the vertex is created on entry and is not
+///   entered now.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VertexDescription {
+    pub name: &'static str,
+    pub sub_machine: Option<MachineDescription>,
+}
+
+/// Description of one transition.
+///
+/// - `source` - the type name of the source vertex, `None` for forall transitions.
+/// - `except` - the vertices that are not affected by a forall transition.
+/// - `events` - type names of the events that the trigger accepts, `_` for catch-all transitions.
+/// - `target` - the type name of the target vertex, `None` for internal transitions, which do not
+///   leave the current vertex.
+/// - `kind` - the kind of the transition.
+/// - `guards` - the number of guards.
+/// - `action` - the name of the action, usually the type name of the function, `None` if the
+///   transition has no action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransitionDescription {
+    pub source: Option<&'static str>,
+    pub except: Vec<&'static str>,
+    pub events: Vec<&'static str>,
+    pub target: Option<&'static str>,
+    pub kind: TransitionKind,
+    pub guards: usize,
+    pub action: Option<&'static str>,
+}
+
+impl TransitionDescription {
+    /// Check that the transition can be started from the vertex with the type name `vertex`.
+    pub fn starts_from(&self, vertex: &str) -> bool {
+        match self.source {
+            Some(source) => source == vertex,
+            None => !self.except.contains(&vertex),
+        }
+    }
+}
+
+/// Describe a state machine.
+pub trait DescribeMachine {
+    fn describe(&self) -> MachineDescription;
+}
+
+impl<C, State, Vertexes, VertHandlers, Transitions, FAllTrans, Answer, GErr> DescribeMachine
+    for StateMachine<C, State, Vertexes, VertHandlers, Transitions, FAllTrans, Answer, GErr>
+where
+    VertHandlers: DescribeVertices<Vertexes>,
+    Transitions: DescribeTransitions,
+    FAllTrans: DescribeTransitions,
+{
+    fn describe(&self) -> MachineDescription {
+        let mut vertices = Vec::new();
+        self.vertices_handlers
+            .describe(&self.vertexes, &mut vertices);
+        let mut transitions = Vec::new();
+        self.transitions.describe(&mut transitions);
+        self.forall_transitions.describe(&mut transitions);
+        MachineDescription {
+            vertices,
+            transitions,
+        }
+    }
+}
+
+/// Describe all vertices of a list. It is implemented for the list of vertex handlers, which
+/// tells which vertices are sub state machines.
+pub trait DescribeVertices<Vertexes> {
+    fn describe(&self, vertexes: &Vertexes, out: &mut Vec<VertexDescription>);
+}
+
+impl DescribeVertices<HNil> for HNil {
+    fn describe(&self, _: &HNil, _: &mut Vec<VertexDescription>) {}
+}
+
+impl<V, Policy, VRest, Rest> DescribeVertices<HCons<VertexSlot<V, Policy>, VRest>>
+    for HCons<EmptyVertexHandler, Rest>
+where
+    Rest: DescribeVertices<VRest>,
+{
+    fn describe(
+        &self,
+        vertexes: &HCons<VertexSlot<V, Policy>, VRest>,
+        out: &mut Vec<VertexDescription>,
+    ) {
+        out.push(VertexDescription {
+            name: type_name::<V>(),
+            sub_machine: None,
+        });
+        self.tail.describe(&vertexes.tail, out);
+    }
+}
+
+impl<IDX, SM, Entry, Exit, Policy, VRest, Rest>
+    DescribeVertices<HCons<VertexSlot<StateMachineVertex<IDX, SM, Entry, Exit>, Policy>, VRest>>
+    for HCons<SubStateMachineVertexHandler, Rest>
+where
+    SM: DescribeMachine,
+    Rest: DescribeVertices<VRest>,
+{
+    fn describe(
+        &self,
+        vertexes: &HCons<VertexSlot<StateMachineVertex<IDX, SM, Entry, Exit>, Policy>, VRest>,
+        out: &mut Vec<VertexDescription>,
+    ) {
+        out.push(VertexDescription {
+            name: type_name::<StateMachineVertex<IDX, SM, Entry, Exit>>(),
+            sub_machine: vertexes.head.get().map(|vertex| vertex.sm.describe()),
+        });
+        self.tail.describe(&vertexes.tail, out);
+    }
+}
+
+/// Describe all transitions of a list.
+pub trait DescribeTransitions {
+    fn describe(&self, out: &mut Vec<TransitionDescription>);
+}
+
+fn events<Trig: Trigger>() -> Vec<&'static str> {
+    let mut events = Vec::new();
+    Trig::event_names(&mut events);
+    events
+}
+
+impl DescribeTransitions for HNil {
+    fn describe(&self, _: &mut Vec<TransitionDescription>) {}
+}
+
+impl<T: DescribeTransitions, Rest: DescribeTransitions> DescribeTransitions for HCons<T, Rest> {
+    fn describe(&self, out: &mut Vec<TransitionDescription>) {
+        self.head.describe(out);
+        self.tail.describe(out);
+    }
+}
+
+impl DescribeTransitions for HMapNil {
+    fn describe(&self, _: &mut Vec<TransitionDescription>) {}
+}
+
+impl<Source, T, Rest> DescribeTransitions for HCons<(PhantomData<Source>, T), Rest>
+where
+    T: DescribeTransitions,
+    Rest: DescribeTransitions,
+{
+    fn describe(&self, out: &mut Vec<TransitionDescription>) {
+        self.head.1.describe(out);
+        self.tail.describe(out);
+    }
+}
+
+impl<H: DescribeTransitions> DescribeTransitions for HMap<H> {
+    fn describe(&self, out: &mut Vec<TransitionDescription>) {
+        self.hlist.describe(out);
+    }
+}
+
+impl<Source, Ctx, Trig, A, G, Target, Answer, GErr> DescribeTransitions
+    for Transition<Source, Ctx, Trig, A, G, Target, Answer, GErr>
+where
+    Trig: Trigger,
+    A: for<'a> Action<Source, Ctx, Trig::Event<'a>, Target, Answer>,
+    G: for<'a> Guard<Trig::Event<'a>, GErr>,
+{
+    fn describe(&self, out: &mut Vec<TransitionDescription>) {
+        out.push(TransitionDescription {
+            source: Some(type_name::<Source>()),
+            except: Vec::new(),
+            events: events::<Trig>(),
+            target: Some(type_name::<Target>()),
+            kind: TransitionKind::Normal,
+            guards: Guard::<Trig::Event<'_>, GErr>::count(&self.guard),
+            action: Action::<Source, Ctx, Trig::Event<'_>, Target, Answer>::name(&self.action),
+        });
+    }
+}
+
+impl<Vertex, Ctx, Trig, A, G, Answer, GErr> DescribeTransitions
+    for LoopTransition<Vertex, Ctx, Trig, A, G, Answer, GErr>
+where
+    Trig: Trigger,
+    A: for<'a> ActionLoop<Vertex, Ctx, Trig::Event<'a>, Answer>,
+    G: for<'a> Guard<Trig::Event<'a>, GErr>,
+{
+    fn describe(&self, out: &mut Vec<TransitionDescription>) {
+        out.push(TransitionDescription {
+            source: Some(type_name::<Vertex>()),
+            except: Vec::new(),
+            events: events::<Trig>(),
+            target: Some(type_name::<Vertex>()),
+            kind: TransitionKind::Loop,
+            guards: Guard::<Trig::Event<'_>, GErr>::count(&self.guard),
+            action: ActionLoop::<Vertex, Ctx, Trig::Event<'_>, Answer>::name(&self.action),
+        });
+    }
+}
+
+impl<Source, Ctx, E, A, G, Target, Answer, GErr> DescribeTransitions
+    for OwnedTransition<Source, Ctx, E, A, G, Target, Answer, GErr>
+where
+    A: OwnedAction<Source, Ctx, E, Target, Answer>,
+    G: Guard<E, GErr>,
+{
+    fn describe(&self, out: &mut Vec<TransitionDescription>) {
+        out.push(TransitionDescription {
+            source: Some(type_name::<Source>()),
+            except: Vec::new(),
+            events: vec![type_name::<E>()],
+            target: Some(type_name::<Target>()),
+            kind: TransitionKind::Normal,
+            guards: Guard::<E, GErr>::count(&self.guard),
+            action: OwnedAction::<Source, Ctx, E, Target, Answer>::name(&self.action),
+        });
+    }
+}
+
+impl<Vertex, Ctx, E, A, G, Answer, GErr> DescribeTransitions
+    for OwnedLoopTransition<Vertex, Ctx, E, A, G, Answer, GErr>
+where
+    A: OwnedActionLoop<Vertex, Ctx, E, Answer>,
+    G: Guard<E, GErr>,
+{
+    fn describe(&self, out: &mut Vec<TransitionDescription>) {
+        out.push(TransitionDescription {
+            source: Some(type_name::<Vertex>()),
+            except: Vec::new(),
+            events: vec![type_name::<E>()],
+            target: Some(type_name::<Vertex>()),
+            kind: TransitionKind::Loop,
+            guards: Guard::<E, GErr>::count(&self.guard),
+            action: OwnedActionLoop::<Vertex, Ctx, E, Answer>::name(&self.action),
+        });
+    }
+}
+
+impl<Ctx, Trig, A, G, Target, Answer, GErr, Except: TypeList> DescribeTransitions
+    for ForallTransition<Ctx, Trig, A, G, Target, Answer, GErr, Except>
+where
+    Trig: Trigger,
+    A: for<'a> ForallAction<Ctx, Trig::Event<'a>, Target, Answer>,
+    G: for<'a> Guard<Trig::Event<'a>, GErr>,
+{
+    fn describe(&self, out: &mut Vec<TransitionDescription>) {
+        let mut except = vec![type_name::<Target>()];
+        Except::names(&mut except);
+        out.push(TransitionDescription {
+            source: None,
+            except,
+            events: events::<Trig>(),
+            target: Some(type_name::<Target>()),
+            kind: TransitionKind::Forall,
+            guards: Guard::<Trig::Event<'_>, GErr>::count(&self.guard),
+            action: ForallAction::<Ctx, Trig::Event<'_>, Target, Answer>::name(&self.action),
+        });
+    }
+}
+
+impl<Ctx, Trig, A, G, Answer, GErr, Except: TypeList> DescribeTransitions
+    for ForallInternalTransition<Ctx, Trig, A, G, Answer, GErr, Except>
+where
+    Trig: Trigger,
+    A: for<'a> InternalAction<Ctx, Trig::Event<'a>, Answer>,
+    G: for<'a> Guard<Trig::Event<'a>, GErr>,
+{
+    fn describe(&self, out: &mut Vec<TransitionDescription>) {
+        let mut except = Vec::new();
+        Except::names(&mut except);
+        out.push(TransitionDescription {
+            source: None,
+            except,
+            events: events::<Trig>(),
+            target: None,
+            kind: TransitionKind::Forall,
+            guards: Guard::<Trig::Event<'_>, GErr>::count(&self.guard),
+            action: InternalAction::<Ctx, Trig::Event<'_>, Answer>::name(&self.action),
+        });
+    }
+}
+
+impl<Vertex, Trig> DescribeTransitions for IgnoreTransition<Vertex, Trig> {
+    fn describe(&self, _: &mut Vec<TransitionDescription>) {}
+}
+
+impl<Vertex, Trig> DescribeTransitions for DeferTransition<Vertex, Trig> {
+    fn describe(&self, _: &mut Vec<TransitionDescription>) {}
+}
+
+impl<Source, Trans: DescribeTransitions> DescribeTransitions for CatchAllTransition<Source, Trans> {
+    fn describe(&self, out: &mut Vec<TransitionDescription>) {
+        let start = out.len();
+        self.transition.describe(out);
+        for description in &mut out[start..] {
+            description.source = Some(type_name::<Source>());
+            description.except.clear();
+            description.kind = TransitionKind::CatchAll;
+        }
+    }
+}
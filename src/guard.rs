@@ -14,7 +14,51 @@
 //! guard_is_odd.check(&1).unwrap();
 //! assert_eq!(guard_is_odd.check(&2), Err("is not odd!"));
 //! ```
+//!
+//! Guards of one `StateMachine` may return different error types. `state_machine!` wraps every
+//! guard in `ErrInto`, which converts its error into `GErr` of the state machine by `Into`:
+//! ```
+//! use umlsm::vertex::{EntryVertex, ExitVertex, InitialPseudoState};
+//! use umlsm::{ProcessEvent, ProcessResult};
+//!
+//! #[derive(Debug, PartialEq)]
+//! enum DoorError {
+//!     Closed(&'static str),
+//!     TooHeavy(u32),
+//! }
+//!
+//! impl From<&'static str> for DoorError {
+//!     fn from(reason: &'static str) -> Self {
+//!         DoorError::Closed(reason)
+//!     }
+//! }
+//!
+//! struct Open;
+//! impl EntryVertex for Open {}
+//! impl ExitVertex for Open {}
+//!
+//! struct Push(u32);
+//!
+//! fn not_locked(_: &Push) -> Result<(), &'static str> {
+//!     Err("locked")
+//! }
+//!
+//! fn light(push: &Push) -> Result<(), DoorError> {
+//!     if push.0 < 10 { Ok(()) } else { Err(DoorError::TooHeavy(push.0)) }
+//! }
+//!
+//! #[rustfmt::skip]
+//! let mut sm = umlsm::state_machine!(
+//!     state = (), err = DoorError,
+//!     [Open],
+//!
+//!     InitialPseudoState + Push [light, not_locked] => Open;
+//! );
+//! assert!(matches!(sm.process(&Push(20)), ProcessResult::GuardErr(e) if e.error == DoorError::TooHeavy(20)));
+//! assert!(matches!(sm.process(&Push(1)), ProcessResult::GuardErr(e) if e.error == DoorError::Closed("locked")));
+//! ```
 use frunk::{HCons, HNil};
+use std::marker::PhantomData;
 
 /// An interface for transition guards.
 ///
@@ -23,26 +67,51 @@ use frunk::{HCons, HNil};
 /// `StateMachine::process` will return `Error` if `Guard` returns `Err(Error)`.
 ///
 /// More about guards: https://en.wikipedia.org/wiki/UML_state_machine#Guard_conditions
-pub trait Guard<Input, Err> {
+pub trait Guard<Input: ?Sized, Err> {
     fn check(&self, input: &Input) -> Result<(), Err>;
+
+    /// The name of the guard for `GuardRejection`. Guards that are functions are named by the
+    /// function, other guards can be named by `Named`.
+    fn name(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Same as `check`, but returns the index of the guard that rejects the input in the list of
+    /// guards and its name with the error.
+    fn check_each(&self, input: &Input) -> Result<(), (usize, Option<&'static str>, Err)> {
+        self.check(input).map_err(|e| (0, self.name(), e))
+    }
+
+    /// The number of guards, which is more than one for a list of guards.
+    fn count(&self) -> usize {
+        1
+    }
 }
 
-impl<Input, F, Err> Guard<Input, Err> for F
+impl<Input: ?Sized, F, Err> Guard<Input, Err> for F
 where
     F: Fn(&Input) -> Result<(), Err>,
 {
     fn check(&self, input: &Input) -> Result<(), Err> {
         self(input)
     }
+
+    fn name(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<F>())
+    }
 }
 
-impl<Input, Err> Guard<Input, Err> for HNil {
+impl<Input: ?Sized, Err> Guard<Input, Err> for HNil {
     fn check(&self, _: &Input) -> Result<(), Err> {
         Ok(())
     }
+
+    fn count(&self) -> usize {
+        0
+    }
 }
 
-impl<Input, F, Rest, Err> Guard<Input, Err> for HCons<F, Rest>
+impl<Input: ?Sized, F, Rest, Err> Guard<Input, Err> for HCons<F, Rest>
 where
     F: Guard<Input, Err>,
     Rest: Guard<Input, Err>,
@@ -50,4 +119,89 @@ where
     fn check(&self, input: &Input) -> Result<(), Err> {
         self.head.check(input).and_then(|_| self.tail.check(input))
     }
+
+    fn check_each(&self, input: &Input) -> Result<(), (usize, Option<&'static str>, Err)> {
+        self.head.check_each(input)?;
+        self.tail
+            .check_each(input)
+            .map_err(|(index, name, e)| (index + 1, name, e))
+    }
+
+    fn count(&self) -> usize {
+        1 + self.tail.count()
+    }
+}
+
+/// A guard with the name, which is shown by `GuardRejection`.
+///
+/// ```
+/// use umlsm::guard::{Guard, Named};
+///
+/// struct Positive;
+/// impl Guard<i32, &'static str> for Positive {
+///     fn check(&self, num: &i32) -> Result<(), &'static str> {
+///         if *num > 0 { Ok(()) } else { Err("not positive") }
+///     }
+/// }
+///
+/// let guard = Named("positive", Positive);
+/// assert_eq!(Guard::<i32, &str>::name(&guard), Some("positive"));
+/// assert_eq!(guard.check_each(&-1), Err((0, Some("positive"), "not positive")));
+/// ```
+#[derive(Clone)]
+pub struct Named<G>(pub &'static str, pub G);
+
+impl<Input: ?Sized, G, Err> Guard<Input, Err> for Named<G>
+where
+    G: Guard<Input, Err>,
+{
+    fn check(&self, input: &Input) -> Result<(), Err> {
+        self.1.check(input)
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        Some(self.0)
+    }
+}
+
+/// Wraps a guard that returns `Err` into a guard that returns any error `Err` converts into.
+///
+/// `state_machine!` wraps all guards in it, so a guard can be reused by state machines with
+/// different `GErr`.
+pub struct ErrInto<G, Err>(G, PhantomData<Err>);
+
+impl<G, Err> ErrInto<G, Err> {
+    pub fn new(guard: G) -> Self {
+        ErrInto(guard, PhantomData)
+    }
+}
+
+impl<G: Clone, Err> Clone for ErrInto<G, Err> {
+    fn clone(&self) -> Self {
+        ErrInto::new(self.0.clone())
+    }
+}
+
+impl<Input: ?Sized, G, Err, GErr> Guard<Input, GErr> for ErrInto<G, Err>
+where
+    G: Guard<Input, Err>,
+    Err: Into<GErr>,
+{
+    fn check(&self, input: &Input) -> Result<(), GErr> {
+        self.0.check(input).map_err(Into::into)
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.0.name()
+    }
+
+    fn check_each(&self, input: &Input) -> Result<(), (usize, Option<&'static str>, GErr)> {
+        self.0
+            .check_each(input)
+            .map_err(|(index, name, e)| (index, name, e.into()))
+    }
+
+    fn count(&self) -> usize {
+        self.0.count()
+    }
 }
@@ -1,10 +1,8 @@
-use crate::utils::{CoprodWithRef, CoprodWithoutPhantomData};
-use frunk::coproduct::CNil;
 use frunk::hlist::{h_cons, HList, Selector};
 use frunk::indices::{Here, There};
-use frunk::{Coproduct, HCons};
-use std::marker::PhantomData;
+use frunk::HCons;
 
+#[derive(Clone)]
 pub struct HMap<H> {
     pub hlist: H,
 }
@@ -14,69 +12,6 @@ impl HMap<HMapNil> {
     }
 }
 
-pub trait HMapGet<'a, Key, Value, Idx> {
-    fn get(&'a self) -> &'a Value;
-}
-
-impl<'a, Key, Value, Idx, H> HMapGet<'a, Key, Value, Idx> for HMap<H>
-where
-    Key: 'a,
-    H: Selector<(Key, Value), Idx>,
-{
-    fn get(&'a self) -> &'a Value {
-        let (_, v) = self.hlist.get();
-        v
-    }
-}
-
-pub trait HMapGetKeyByCoprod<'a, C, CRes> {
-    fn get_by_coprod(&'a self, c: &C) -> CRes;
-}
-
-impl<'a, C, H>
-    HMapGetKeyByCoprod<
-        'a,
-        C,
-        <<C as CoprodWithoutPhantomData>::WithoutPD as CoprodWithRef<'a>>::CoprodWithRef,
-    > for HMap<H>
-where
-    C: CoprodWithoutPhantomData,
-    C::WithoutPD: CoprodWithRef<'a>,
-    <<C as CoprodWithoutPhantomData>::WithoutPD as CoprodWithRef<'a>>::CoprodWithRef: 'a,
-    H: HMapGetKeyByCoprod<
-        'a,
-        C,
-        <<C as CoprodWithoutPhantomData>::WithoutPD as CoprodWithRef<'a>>::CoprodWithRef,
-    >,
-{
-    fn get_by_coprod<'b>(
-        &'a self,
-        c: &'b C,
-    ) -> <<C as CoprodWithoutPhantomData>::WithoutPD as CoprodWithRef<'a>>::CoprodWithRef {
-        self.hlist.get_by_coprod(c)
-    }
-}
-
-impl<'a> HMapGetKeyByCoprod<'a, CNil, CNil> for HMapNil {
-    fn get_by_coprod(&'a self, c: &CNil) -> CNil {
-        match *c {}
-    }
-}
-
-impl<'a, C, V, CRest, CResRest, HRest>
-    HMapGetKeyByCoprod<'a, Coproduct<PhantomData<C>, CRest>, Coproduct<&'a C, CResRest>>
-    for HCons<(C, V), HRest>
-where
-    HRest: HMapGetKeyByCoprod<'a, CRest, CResRest>,
-{
-    fn get_by_coprod(&'a self, c: &Coproduct<PhantomData<C>, CRest>) -> Coproduct<&'a C, CResRest> {
-        match c {
-            Coproduct::Inl(_) => Coproduct::Inl(&self.head.0),
-            Coproduct::Inr(r) => Coproduct::Inr(self.tail.get_by_coprod(r)),
-        }
-    }
-}
-
 impl<H> HMap<H> {
     pub fn add<Key, Value>(self, key: Key, value: Value) -> HMap<HCons<(Key, Value), H>>
     where
@@ -151,25 +86,7 @@ where
     }
 }
 
-pub trait KeySelector<T, Idx> {
-    fn select_by_key(&mut self) -> &mut T;
-}
-
-impl<K, V, Rest> KeySelector<K, Here> for HCons<(K, V), Rest> {
-    fn select_by_key(&mut self) -> &mut K {
-        &mut self.head.0
-    }
-}
-
-impl<T, This, Idx, Rest> KeySelector<T, There<Idx>> for HCons<This, Rest>
-where
-    Rest: KeySelector<T, Idx>,
-{
-    fn select_by_key(&mut self) -> &mut T {
-        self.tail.select_by_key()
-    }
-}
-
+#[derive(Clone)]
 pub struct HMapNil;
 impl HList for HMapNil {
     const LEN: usize = 0;
@@ -1,10 +1,15 @@
+#![allow(clippy::type_complexity)]
+#![forbid(unsafe_code)]
+
 pub mod action;
-mod guard;
+pub mod description;
+pub mod guard;
 mod hmap;
 mod process_event;
 pub mod process_result;
 mod sm;
 pub mod transition;
+pub mod trigger;
 mod utils;
 pub mod vert_handler;
 pub mod vertex;
@@ -12,9 +17,13 @@ pub mod vertex;
 pub use {
     action::Action,
     guard::Guard,
-    process_event::ProcessEvent,
-    process_result::ProcessResult,
+    process_event::{
+        ProcessEvent, ProcessEventDetailed, ProcessOwnedEvent, ProcessOwnedRequest, ProcessRequest,
+        Request,
+    },
+    process_result::{ProcessError, ProcessResult},
     sm::{CurrentStateIs, StateMachine},
+    utils::StatePath,
 };
 
 #[doc(hidden)]
@@ -28,14 +37,25 @@ macro_rules! state_machine {
     (parse_source, ($some:ty)) => { $some };
     (parse_source, $some:ty) => { $some };
 
-    (parse_action, $source:tt, $event:ty, ) => { $crate::action::EmptyAction::<$crate::state_machine!(parse_source, $source), $event>::new() };
-    (parse_action, $source:tt, $event:ty,$action:expr) => { $action };
+    (parse_priority, (), $action:expr) => { $action };
+    (parse_priority, ($priority:expr), $action:expr) => { $crate::action::Prioritized::new($priority, $action) };
+
+    (parse_answer, plain, $prio:tt, $event:ty, $action:expr) => { $crate::state_machine!(parse_priority, $prio, $action) };
+    (parse_answer, reply, $prio:tt, $event:ty, $action:expr) => { $crate::state_machine!(parse_priority, $prio, $crate::action::Replying::<_, $event>::new($action)) };
+
+    (parse_action, $mode:tt, $prio:tt, $source:tt, $event:ty, ) => { $crate::state_machine!(parse_answer, $mode, $prio, $event, $crate::action::EmptyAction::<$crate::state_machine!(parse_source, $source), $event>::new()) };
+    (parse_action, $mode:tt, $prio:tt, $source:tt, $event:ty,$action:expr) => { $crate::state_machine!(parse_answer, $mode, $prio, $event, $action) };
+
+    (parse_action_loop, $mode:tt, $prio:tt, $source:tt, $event:ty, ) => { $crate::state_machine!(parse_answer, $mode, $prio, $event, $crate::action::EmptyActionLoop::<$crate::state_machine!(parse_source, $source), $event>::new()) };
+    (parse_action_loop, $mode:tt, $prio:tt, $source:tt, $event:ty,$action:expr) => { $crate::state_machine!(parse_answer, $mode, $prio, $event, $action) };
 
-    (parse_action_loop, $source:tt, $event:ty, ) => { $crate::action::EmptyActionLoop::<$crate::state_machine!(parse_source, $source), $event>::new() };
-    (parse_action_loop, $source:tt, $event:ty,$action:expr) => { $action };
+    (parse_action_forall, $mode:tt, $prio:tt, $event:ty, ) => { $crate::state_machine!(parse_answer, $mode, $prio, $event, $crate::action::EmptyForallAction::<$event>::new()) };
+    (parse_action_forall, $mode:tt, $prio:tt, $event:ty,$action:expr) => { $crate::state_machine!(parse_answer, $mode, $prio, $event, $action) };
 
-    (parse_action_forall, $event:ty, ) => { $crate::action::EmptyForallAction::<$event>::new() };
-    (parse_action_forall, $event:ty,$action:expr) => { $action };
+    (parse_action_internal, $mode:tt, $prio:tt, $event:ty, ) => { $crate::state_machine!(parse_answer, $mode, $prio, $event, $crate::action::EmptyInternalAction::<$event>::new()) };
+    (parse_action_internal, $mode:tt, $prio:tt, $event:ty,$action:expr) => { $crate::state_machine!(parse_answer, $mode, $prio, $event, $action) };
+
+    (parse_any_of, $($event:ty)|+) => { $crate::trigger::AnyOf<$crate::reexport::frunk::Hlist![$($event),+]> };
 
     (parse_err, ) => { () };
     (parse_err, $some:ty) => { $some };
@@ -43,38 +63,420 @@ macro_rules! state_machine {
     (parse_v_type, ) => { $crate::vert_handler::EmptyVertexHandler };
     (parse_v_type, Sub) => { $crate::vert_handler::SubStateMachineVertexHandler };
 
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*]) => { $($sm)* };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*] priority($priority:expr) $($rest:tt)*) => {
+        $crate::state_machine!(@transitions $mode ($priority) [$($sm)*] $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*] forall: $($rest:tt)*) => {
+        $crate::state_machine!(@forall $mode () [$($sm)*] $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*] loop: $($rest:tt)*) => {
+        $crate::state_machine!(@loop $mode () [$($sm)*] $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*] ignore: $($rest:tt)*) => {
+        $crate::state_machine!(@ignore $mode [$($sm)*] $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*] defer: $($rest:tt)*) => {
+        $crate::state_machine!(@defer $mode [$($sm)*] $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*] initial => $target:ty $(| $action:expr)?, $($rest:tt)*) => {
+        $crate::state_machine!(@transitions $mode $prio [$($sm)*]
+            ($crate::vertex::InitialPseudoState) + $crate::vertex::Start $(| $action)? => $target, $($rest)*
+        )
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*] initial => $target:ty $(| $action:expr)?; $($rest:tt)*) => {
+        $crate::state_machine!(@transitions $mode $prio [$($sm)*] initial => $target $(| $action)?, $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + _ $([$($guard:expr),*])? $(| $action:expr)? => $target:ty, $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@transitions $mode () [$($sm)*
+            .add_catch_all::<_, _, $crate::state_machine!(parse_source, $source), $target, _>(
+                $crate::state_machine!(parse_action, $mode, $prio, $source, $crate::trigger::Wildcard, $($action)?),
+                $crate::reexport::frunk::hlist![$($($crate::guard::ErrInto::new($guard)),*)?],
+                std::marker::PhantomData,
+            )
+        ] $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + _ $([$($guard:expr),*])? $(| $action:expr)? => $target:ty; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@transitions $mode $prio [$($sm)*] $source + _ $([$($guard),*])? $(| $action)? => $target, $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + _ $([$($guard:expr),*])? | $action:expr, $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@transitions $mode () [$($sm)*
+            .add_internal_catch_all::<_, _, $crate::state_machine!(parse_source, $source)>(
+                $crate::state_machine!(parse_answer, $mode, $prio, $crate::trigger::Wildcard, $action),
+                $crate::reexport::frunk::hlist![$($($crate::guard::ErrInto::new($guard)),*)?],
+            )
+        ] $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + _ $([$($guard:expr),*])? | $action:expr; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@transitions $mode $prio [$($sm)*] $source + _ $([$($guard),*])? | $action, $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + @trigger[$trig:ty] $([$($guard:expr),*])? $(| $action:expr)? => $target:ty, $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@transitions $mode () [$($sm)*
+            .add_transition_with_trigger::<_, _, $crate::state_machine!(parse_source, $source), $trig, $target, _, _, _>(
+                $crate::state_machine!(parse_action, $mode, $prio, $source, $trig, $($action)?),
+                $crate::reexport::frunk::hlist![$($($crate::guard::ErrInto::new($guard)),*)?],
+                std::marker::PhantomData,
+            )
+        ] $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + @trigger[$trig:ty] $([$($guard:expr),*])? $(| $action:expr)? => $target:ty; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@transitions $mode $prio [$($sm)*] $source + @trigger[$trig] $([$($guard),*])? $(| $action)? => $target, $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + move $event:ty $([$($guard:expr),*])? | $action:expr => $target:ty, $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@transitions $mode () [$($sm)*
+            .add_owned_transition::<_, _, $crate::state_machine!(parse_source, $source), $event, $target, _, _, _>(
+                $crate::state_machine!(parse_answer, $mode, $prio, $event, $action),
+                $crate::reexport::frunk::hlist![$($($crate::guard::ErrInto::new($guard)),*)?],
+                std::marker::PhantomData,
+            )
+        ] $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + move $event:ty $([$($guard:expr),*])? | $action:expr => $target:ty; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@transitions $mode $prio [$($sm)*] $source + move $event $([$($guard),*])? | $action => $target, $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*] $source:tt + ($first:ty $(| $event:ty)+) $($rest:tt)*) => {
+        $crate::state_machine!(@transitions $mode $prio [$($sm)*] $source + @trigger[$crate::state_machine!(parse_any_of, $first $(| $event)+)] $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*] $source:tt + $($name:ident)::+ <'_> $($rest:tt)*) => {
+        $crate::state_machine!(@transitions $mode $prio [$($sm)*] $source + @trigger[$crate::trigger::Borrowed<$($name)::+<'static>>] $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + dyn $($family:ident)::+ $([$($guard:expr),*])? $(| $action:expr)? => $target:ty, $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@transitions $mode $prio [$($sm)*]
+            $source + @trigger[$crate::trigger::FamilyOf<dyn $($family)::+>]
+            $([$($crate::trigger::FamilyGuard::new($guard)),*])? $(| $crate::trigger::FamilyAction::new($action))? => $target, $($rest)*
+        )
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + dyn $($family:ident)::+ $([$($guard:expr),*])? $(| $action:expr)? => $target:ty; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@transitions $mode $prio [$($sm)*] $source + dyn $($family)::+ $([$($guard),*])? $(| $action)? => $target, $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + $event:ty $([$($guard:expr),*])? $(| $action:expr)? => $target:ty, $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@transitions $mode () [$($sm)*
+            .add_transition::<_, _, _, $crate::state_machine!(parse_source, $source), $event, $target, _, _, _>(
+                $crate::state_machine!(parse_action, $mode, $prio, $source, $event, $($action)?),
+                $crate::reexport::frunk::hlist![$($($crate::guard::ErrInto::new($guard)),*)?],
+                std::marker::PhantomData,
+            )
+        ] $($rest)*)
+    };
+    (@transitions $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + $event:ty $([$($guard:expr),*])? $(| $action:expr)? => $target:ty; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@transitions $mode $prio [$($sm)*] $source + $event $([$($guard),*])? $(| $action)? => $target, $($rest)*)
+    };
+
+    (@forall $mode:tt $prio:tt [$($sm:tt)*]) => { $($sm)* };
+    (@forall $mode:tt $prio:tt [$($sm:tt)*] priority($priority:expr) $($rest:tt)*) => {
+        $crate::state_machine!(@forall $mode ($priority) [$($sm)*] $($rest)*)
+    };
+    (@forall $mode:tt $prio:tt [$($sm:tt)*] loop: $($rest:tt)*) => {
+        $crate::state_machine!(@loop $mode () [$($sm)*] $($rest)*)
+    };
+    (@forall $mode:tt $prio:tt [$($sm:tt)*] ignore: $($rest:tt)*) => {
+        $crate::state_machine!(@ignore $mode [$($sm)*] $($rest)*)
+    };
+    (@forall $mode:tt $prio:tt [$($sm:tt)*] defer: $($rest:tt)*) => {
+        $crate::state_machine!(@defer $mode [$($sm)*] $($rest)*)
+    };
+    (@forall $mode:tt $prio:tt [$($sm:tt)*]
+        $(except [$($except:ty),*])? + @trigger[$trig:ty] $([$($guard:expr),*])? $(| $action:expr)? => $target:ty; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@forall $mode () [$($sm)*
+            .add_transition_forall_with_trigger::<_, _, $trig, $target, $crate::reexport::frunk::Hlist![$($($except),*)?]>(
+                $crate::state_machine!(parse_action_forall, $mode, $prio, $trig, $($action)?),
+                $crate::reexport::frunk::hlist![$($($crate::guard::ErrInto::new($guard)),*)?],
+                std::marker::PhantomData,
+                std::marker::PhantomData,
+            )
+        ] $($rest)*)
+    };
+    (@forall $mode:tt $prio:tt [$($sm:tt)*]
+        $(except [$($except:ty),*])? + @trigger[$trig:ty] $([$($guard:expr),*])? $(| $action:expr)?; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@forall $mode () [$($sm)*
+            .add_internal_forall_with_trigger::<_, _, $trig, $crate::reexport::frunk::Hlist![$($($except),*)?]>(
+                $crate::state_machine!(parse_action_internal, $mode, $prio, $trig, $($action)?),
+                $crate::reexport::frunk::hlist![$($($crate::guard::ErrInto::new($guard)),*)?],
+                std::marker::PhantomData,
+            )
+        ] $($rest)*)
+    };
+    (@forall $mode:tt $prio:tt [$($sm:tt)*] $(except [$($except:ty),*])? + ($first:ty $(| $event:ty)+) $($rest:tt)*) => {
+        $crate::state_machine!(@forall $mode $prio [$($sm)*] $(except [$($except),*])? + @trigger[$crate::state_machine!(parse_any_of, $first $(| $event)+)] $($rest)*)
+    };
+    (@forall $mode:tt $prio:tt [$($sm:tt)*] $(except [$($except:ty),*])? + $($name:ident)::+ <'_> $($rest:tt)*) => {
+        $crate::state_machine!(@forall $mode $prio [$($sm)*] $(except [$($except),*])? + @trigger[$crate::trigger::Borrowed<$($name)::+<'static>>] $($rest)*)
+    };
+    (@forall $mode:tt $prio:tt [$($sm:tt)*]
+        $(except [$($except:ty),*])? + dyn $($family:ident)::+ $([$($guard:expr),*])? $(| $action:expr)? $(=> $target:ty)?; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@forall $mode $prio [$($sm)*]
+            $(except [$($except),*])? + @trigger[$crate::trigger::FamilyOf<dyn $($family)::+>]
+            $([$($crate::trigger::FamilyGuard::new($guard)),*])? $(| $crate::trigger::FamilyAction::new($action))? $(=> $target)?; $($rest)*
+        )
+    };
+    (@forall $mode:tt $prio:tt [$($sm:tt)*]
+        $(except [$($except:ty),*])? + $event:ty $([$($guard:expr),*])? $(| $action:expr)? => $target:ty; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@forall $mode () [$($sm)*
+            .add_transition_forall_except::<_, _, $event, $target, $crate::reexport::frunk::Hlist![$($($except),*)?]>(
+                $crate::state_machine!(parse_action_forall, $mode, $prio, $event, $($action)?),
+                $crate::reexport::frunk::hlist![$($($crate::guard::ErrInto::new($guard)),*)?],
+                std::marker::PhantomData,
+                std::marker::PhantomData,
+            )
+        ] $($rest)*)
+    };
+    (@forall $mode:tt $prio:tt [$($sm:tt)*]
+        $(except [$($except:ty),*])? + $event:ty $([$($guard:expr),*])? $(| $action:expr)?; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@forall $mode () [$($sm)*
+            .add_internal_forall::<_, _, $event, $crate::reexport::frunk::Hlist![$($($except),*)?]>(
+                $crate::state_machine!(parse_action_internal, $mode, $prio, $event, $($action)?),
+                $crate::reexport::frunk::hlist![$($($crate::guard::ErrInto::new($guard)),*)?],
+                std::marker::PhantomData,
+            )
+        ] $($rest)*)
+    };
+
+    (@loop $mode:tt $prio:tt [$($sm:tt)*]) => { $($sm)* };
+    (@loop $mode:tt $prio:tt [$($sm:tt)*] priority($priority:expr) $($rest:tt)*) => {
+        $crate::state_machine!(@loop $mode ($priority) [$($sm)*] $($rest)*)
+    };
+    (@loop $mode:tt $prio:tt [$($sm:tt)*] ignore: $($rest:tt)*) => {
+        $crate::state_machine!(@ignore $mode [$($sm)*] $($rest)*)
+    };
+    (@loop $mode:tt $prio:tt [$($sm:tt)*] defer: $($rest:tt)*) => {
+        $crate::state_machine!(@defer $mode [$($sm)*] $($rest)*)
+    };
+    (@loop $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + @trigger[$trig:ty] $([$($guard:expr),*])? $(| $action:expr)?, $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@loop $mode () [$($sm)*
+            .add_loop_with_trigger::<_, _, $crate::state_machine!(parse_source, $source), $trig, _, _>(
+                $crate::state_machine!(parse_action_loop, $mode, $prio, $source, $trig, $($action)?),
+                $crate::reexport::frunk::hlist![$($($crate::guard::ErrInto::new($guard)),*)?],
+            )
+        ] $($rest)*)
+    };
+    (@loop $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + @trigger[$trig:ty] $([$($guard:expr),*])? $(| $action:expr)?; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@loop $mode $prio [$($sm)*] $source + @trigger[$trig] $([$($guard),*])? $(| $action)?, $($rest)*)
+    };
+    (@loop $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + move $event:ty $([$($guard:expr),*])? | $action:expr, $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@loop $mode () [$($sm)*
+            .add_owned_loop::<_, _, $crate::state_machine!(parse_source, $source), $event, _, _>(
+                $crate::state_machine!(parse_answer, $mode, $prio, $event, $action),
+                $crate::reexport::frunk::hlist![$($($crate::guard::ErrInto::new($guard)),*)?],
+            )
+        ] $($rest)*)
+    };
+    (@loop $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + move $event:ty $([$($guard:expr),*])? | $action:expr; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@loop $mode $prio [$($sm)*] $source + move $event $([$($guard),*])? | $action, $($rest)*)
+    };
+    (@loop $mode:tt $prio:tt [$($sm:tt)*] $source:tt + ($first:ty $(| $event:ty)+) $($rest:tt)*) => {
+        $crate::state_machine!(@loop $mode $prio [$($sm)*] $source + @trigger[$crate::state_machine!(parse_any_of, $first $(| $event)+)] $($rest)*)
+    };
+    (@loop $mode:tt $prio:tt [$($sm:tt)*] $source:tt + $($name:ident)::+ <'_> $($rest:tt)*) => {
+        $crate::state_machine!(@loop $mode $prio [$($sm)*] $source + @trigger[$crate::trigger::Borrowed<$($name)::+<'static>>] $($rest)*)
+    };
+    (@loop $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + dyn $($family:ident)::+ $([$($guard:expr),*])? $(| $action:expr)?, $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@loop $mode $prio [$($sm)*]
+            $source + @trigger[$crate::trigger::FamilyOf<dyn $($family)::+>]
+            $([$($crate::trigger::FamilyGuard::new($guard)),*])? $(| $crate::trigger::FamilyAction::new($action))?, $($rest)*
+        )
+    };
+    (@loop $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + dyn $($family:ident)::+ $([$($guard:expr),*])? $(| $action:expr)?; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@loop $mode $prio [$($sm)*] $source + dyn $($family)::+ $([$($guard),*])? $(| $action)?, $($rest)*)
+    };
+    (@loop $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + $event:ty $([$($guard:expr),*])? $(| $action:expr)?, $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@loop $mode () [$($sm)*
+            .add_loop::<_, _, $crate::state_machine!(parse_source, $source), $event, _, _>(
+                $crate::state_machine!(parse_action_loop, $mode, $prio, $source, $event, $($action)?),
+                $crate::reexport::frunk::hlist![$($($crate::guard::ErrInto::new($guard)),*)?],
+            )
+        ] $($rest)*)
+    };
+    (@loop $mode:tt $prio:tt [$($sm:tt)*]
+        $source:tt + $event:ty $([$($guard:expr),*])? $(| $action:expr)?; $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@loop $mode $prio [$($sm)*] $source + $event $([$($guard),*])? $(| $action)?, $($rest)*)
+    };
+
+    (@ignore $mode:tt [$($sm:tt)*]) => { $($sm)* };
+    (@ignore $mode:tt [$($sm:tt)*] forall: $($rest:tt)*) => {
+        $crate::state_machine!(@forall $mode () [$($sm)*] $($rest)*)
+    };
+    (@ignore $mode:tt [$($sm:tt)*] loop: $($rest:tt)*) => {
+        $crate::state_machine!(@loop $mode () [$($sm)*] $($rest)*)
+    };
+    (@ignore $mode:tt [$($sm:tt)*] defer: $($rest:tt)*) => {
+        $crate::state_machine!(@defer $mode [$($sm)*] $($rest)*)
+    };
+    (@ignore $mode:tt [$($sm:tt)*] $source:tt + @trigger[$trig:ty] $(, $($rest:tt)*)?) => {
+        $crate::state_machine!(@ignore $mode [$($sm)*
+            .add_ignore_with_trigger::<$crate::state_machine!(parse_source, $source), $trig, _, _>()
+        ] $($($rest)*)?)
+    };
+    (@ignore $mode:tt [$($sm:tt)*] $source:tt + @trigger[$trig:ty]; $($rest:tt)*) => {
+        $crate::state_machine!(@ignore $mode [$($sm)*] $source + @trigger[$trig], $($rest)*)
+    };
+    (@ignore $mode:tt [$($sm:tt)*] $source:tt + ($first:ty $(| $event:ty)+) $($rest:tt)*) => {
+        $crate::state_machine!(@ignore $mode [$($sm)*] $source + @trigger[$crate::state_machine!(parse_any_of, $first $(| $event)+)] $($rest)*)
+    };
+    (@ignore $mode:tt [$($sm:tt)*] $source:tt + $($name:ident)::+ <'_> $($rest:tt)*) => {
+        $crate::state_machine!(@ignore $mode [$($sm)*] $source + @trigger[$crate::trigger::Borrowed<$($name)::+<'static>>] $($rest)*)
+    };
+    (@ignore $mode:tt [$($sm:tt)*] $source:tt + dyn $($family:ident)::+ $($rest:tt)*) => {
+        $crate::state_machine!(@ignore $mode [$($sm)*] $source + @trigger[$crate::trigger::FamilyOf<dyn $($family)::+>] $($rest)*)
+    };
+    (@ignore $mode:tt [$($sm:tt)*] $source:tt + $event:ty $(, $($rest:tt)*)?) => {
+        $crate::state_machine!(@ignore $mode [$($sm)*
+            .add_ignore::<$crate::state_machine!(parse_source, $source), $event, _, _>()
+        ] $($($rest)*)?)
+    };
+    (@ignore $mode:tt [$($sm:tt)*] $source:tt + $event:ty; $($rest:tt)*) => {
+        $crate::state_machine!(@ignore $mode [$($sm)*] $source + $event, $($rest)*)
+    };
+
+    (@defer $mode:tt [$($sm:tt)*]) => { $($sm)* };
+    (@defer $mode:tt [$($sm:tt)*] forall: $($rest:tt)*) => {
+        $crate::state_machine!(@forall $mode () [$($sm)*] $($rest)*)
+    };
+    (@defer $mode:tt [$($sm:tt)*] loop: $($rest:tt)*) => {
+        $crate::state_machine!(@loop $mode () [$($sm)*] $($rest)*)
+    };
+    (@defer $mode:tt [$($sm:tt)*] ignore: $($rest:tt)*) => {
+        $crate::state_machine!(@ignore $mode [$($sm)*] $($rest)*)
+    };
+    (@defer $mode:tt [$($sm:tt)*] $source:tt + @trigger[$trig:ty] $(, $($rest:tt)*)?) => {
+        $crate::state_machine!(@defer $mode [$($sm)*
+            .add_defer_with_trigger::<$crate::state_machine!(parse_source, $source), $trig, _, _>()
+        ] $($($rest)*)?)
+    };
+    (@defer $mode:tt [$($sm:tt)*] $source:tt + @trigger[$trig:ty]; $($rest:tt)*) => {
+        $crate::state_machine!(@defer $mode [$($sm)*] $source + @trigger[$trig], $($rest)*)
+    };
+    (@defer $mode:tt [$($sm:tt)*] $source:tt + ($first:ty $(| $event:ty)+) $($rest:tt)*) => {
+        $crate::state_machine!(@defer $mode [$($sm)*] $source + @trigger[$crate::state_machine!(parse_any_of, $first $(| $event)+)] $($rest)*)
+    };
+    (@defer $mode:tt [$($sm:tt)*] $source:tt + $($name:ident)::+ <'_> $($rest:tt)*) => {
+        $crate::state_machine!(@defer $mode [$($sm)*] $source + @trigger[$crate::trigger::Borrowed<$($name)::+<'static>>] $($rest)*)
+    };
+    (@defer $mode:tt [$($sm:tt)*] $source:tt + dyn $($family:ident)::+ $($rest:tt)*) => {
+        $crate::state_machine!(@defer $mode [$($sm)*] $source + @trigger[$crate::trigger::FamilyOf<dyn $($family)::+>] $($rest)*)
+    };
+    (@defer $mode:tt [$($sm:tt)*] $source:tt + $event:ty $(, $($rest:tt)*)?) => {
+        $crate::state_machine!(@defer $mode [$($sm)*
+            .add_defer::<$crate::state_machine!(parse_source, $source), $event, _, _>()
+        ] $($($rest)*)?)
+    };
+    (@defer $mode:tt [$($sm:tt)*] $source:tt + $event:ty; $($rest:tt)*) => {
+        $crate::state_machine!(@defer $mode [$($sm)*] $source + $event, $($rest)*)
+    };
+
+    (@vertices $mode:tt [$($sm:tt)*] [] $($rest:tt)*) => {
+        $crate::state_machine!(@transitions $mode () [$($sm)*] $($rest)*)
+    };
+    (@vertices $mode:tt [$($sm:tt)*] [$(@$type:ident)? reset: Default($vertex:ty) $(, $($tail:tt)*)?] $($rest:tt)*) => {
+        $crate::state_machine!(@vertices $mode [$($sm)*
+            .add_vertex_slot(
+                $crate::vertex::VertexSlot::<$vertex, _>::reset_default(),
+                $crate::state_machine!(parse_v_type, $($type)?),
+            )
+        ] [$($($tail)*)?] $($rest)*)
+    };
+    (@vertices $mode:tt [$($sm:tt)*] [$(@$type:ident)? reset: Clone($vertex:expr) $(, $($tail:tt)*)?] $($rest:tt)*) => {
+        $crate::state_machine!(@vertices $mode [$($sm)*
+            .add_vertex_slot(
+                $crate::vertex::VertexSlot::reset_clone($vertex),
+                $crate::state_machine!(parse_v_type, $($type)?),
+            )
+        ] [$($($tail)*)?] $($rest)*)
+    };
+    (@vertices $mode:tt [$($sm:tt)*] [$(@$type:ident)? retain($vertex:expr) $(, $($tail:tt)*)?] $($rest:tt)*) => {
+        $crate::state_machine!(@vertices $mode [$($sm)*] [$(@$type)? $vertex $(, $($tail)*)?] $($rest)*)
+    };
+    (@vertices $mode:tt [$($sm:tt)*] [@$type:ident $vertex:expr $(, $($tail:tt)*)?] $($rest:tt)*) => {
+        $crate::state_machine!(@vertices $mode [$($sm)*
+            .add_vertex($vertex, $crate::state_machine!(parse_v_type, $type))
+        ] [$($($tail)*)?] $($rest)*)
+    };
+    (@vertices $mode:tt [$($sm:tt)*] [$factory:expr => $vertex:ty $(, $($tail:tt)*)?] $($rest:tt)*) => {
+        $crate::state_machine!(@vertices $mode [$($sm)*
+            .add_vertex_on_entry::<$vertex, _, _, _>($factory, $crate::vert_handler::EmptyVertexHandler)
+        ] [$($($tail)*)?] $($rest)*)
+    };
+    (@vertices $mode:tt [$($sm:tt)*] [$vertex:expr $(, $($tail:tt)*)?] $($rest:tt)*) => {
+        $crate::state_machine!(@vertices $mode [$($sm)*
+            .add_vertex($vertex, $crate::state_machine!(parse_v_type, ))
+        ] [$($($tail)*)?] $($rest)*)
+    };
+
     (
         state = $state:expr
         $(, err = $err:ty)?,
-        [$($(@$type:ident)?$vertex:expr),*],
-        $($($source:tt + $event:ty $([$($guard:expr),*])? $(| $action:expr)? => $target:ty),*;)?
-        $(forall: $(+ $event3:ty $([$($guard3:expr),*])? $(| $action3:expr)? => $target3:ty;)+)?
-        $(loop: $($source2:tt + $event2:ty $([$($guard2:expr),*])? $(| $action2:expr)?),*;)?
-    ) => {
-        $crate::StateMachine::<_, _, _, _, _, _, _, $crate::state_machine!(parse_err, $($err)?)>::new($state)
-            $(.add_vertex($vertex, $crate::state_machine!(parse_v_type, $($type)?)))*
-            $($(.add_transition::<_, _, _, $crate::state_machine!(parse_source, $source), $event, $target, _, _, _>(
-                $crate::state_machine!(parse_action, $source, $event, $($action)?),
-                $crate::reexport::frunk::hlist![$($($guard),*)?],
-                std::marker::PhantomData,
-            ))*)?
-            $($(.add_transition_forall::<_, _, $event3, $target3>(
-                $crate::state_machine!(parse_action_forall, $event3, $($action3)?),
-                $crate::reexport::frunk::hlist![$($($guard3),*)?],
-                std::marker::PhantomData,
-            ))+)?
-            $($(.add_loop::<_, _, $crate::state_machine!(parse_source, $source2), $event2, _, _>(
-                $crate::state_machine!(parse_action_loop, $source2, $event2, $($action2)?),
-                $crate::reexport::frunk::hlist![$($($guard2),*)?],
-            ))*)?
+        answer = Reply,
+        [$($vertices:tt)*],
+        $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@vertices reply [
+            $crate::StateMachine::<_, _, _, _, _, _, $crate::action::Reply, $crate::state_machine!(parse_err, $($err)?)>::new($state)
+        ] [$($vertices)*] $($rest)*)
+    };
+    (
+        state = $state:expr
+        $(, err = $err:ty)?,
+        [$($vertices:tt)*],
+        $($rest:tt)*
+    ) => {
+        $crate::state_machine!(@vertices plain [
+            $crate::StateMachine::<_, _, _, _, _, _, _, $crate::state_machine!(parse_err, $($err)?)>::new($state)
+        ] [$($vertices)*] $($rest)*)
     };
 }
 
 #[cfg(test)]
 mod tests {
     use crate::sm::CurrentStateIs;
-    use crate::vertex::{EntryVertex, ExitVertex, InitialPseudoState, TerminationPseudoState};
+    use crate::trigger::Unhandled;
+    use crate::vertex::{
+        EntryVertex, ExitVertex, Final, InitialPseudoState, Start, TerminationPseudoState,
+    };
     use crate::ProcessEvent;
+    use frunk::Coprod;
 
     struct Locked;
     impl EntryVertex for Locked {
@@ -165,4 +567,1240 @@ mod tests {
         sm.process(&BEvent).unwrap();
         assert!(sm.is::<TerminationPseudoState>());
     }
+
+    struct Reset;
+    struct Stop;
+
+    #[test]
+    fn test_forall() {
+        let mut sm = state_machine!(
+            state = 0u32, err = (),
+            [Locked, Unlocked],
+
+            InitialPseudoState + ()   => Locked,
+            Locked             + Push => Unlocked;
+
+            forall:
+            + Reset | |count: &mut u32, _: &Reset, _: &mut Locked| *count += 1 => Locked;
+            + Stop                                                               => TerminationPseudoState;
+        );
+
+        sm.process(&()).unwrap();
+        sm.process(&Push).unwrap();
+        assert!(sm.is::<Unlocked>());
+
+        sm.process(&Reset).unwrap();
+        assert!(sm.is::<Locked>());
+        assert_eq!(*sm.state(), 1);
+
+        assert!(!sm.process(&Reset).is_handled());
+
+        sm.process(&Stop).unwrap();
+        assert!(sm.is::<TerminationPseudoState>());
+    }
+
+    struct Help;
+
+    #[test]
+    fn test_forall_except_and_internal() {
+        let mut sm = state_machine!(
+            state = 0u32, err = (),
+            [Locked, Unlocked],
+
+            InitialPseudoState + ()   => Locked,
+            Locked             + Push => Unlocked;
+
+            forall:
+            except [Unlocked] + Stop                                  => TerminationPseudoState;
+            + Help | |count: &mut u32, _: &Help| *count += 1;
+        );
+
+        sm.process(&()).unwrap();
+        sm.process(&Help).unwrap();
+        assert!(sm.is::<Locked>());
+        assert_eq!(*sm.state(), 1);
+
+        sm.process(&Push).unwrap();
+        sm.process(&Help).unwrap();
+        assert!(sm.is::<Unlocked>());
+        assert_eq!(*sm.state(), 2);
+
+        assert!(!sm.process(&Stop).is_handled());
+        assert!(sm.is::<Unlocked>());
+    }
+
+    struct Kick;
+
+    fn count_kicks(_: &mut Unlocked, count: &mut u32, _: &Coprod!(&Push, &Kick)) {
+        *count += 1;
+    }
+
+    #[test]
+    fn test_any_of() {
+        let mut sm = state_machine!(
+            state = 0u32, err = (),
+            [Locked, Unlocked],
+
+            InitialPseudoState + ()            => Locked,
+            Locked             + (Push | Kick) => Unlocked,
+            Unlocked           + (Reset | Stop) => Locked;
+
+            loop:
+            Unlocked + (Push | Kick) | count_kicks;
+        );
+
+        sm.process(&()).unwrap();
+        sm.process(&Kick).unwrap();
+        assert!(sm.is::<Unlocked>());
+
+        sm.process(&Push).unwrap();
+        sm.process(&Kick).unwrap();
+        assert!(sm.is::<Unlocked>());
+        assert_eq!(*sm.state(), 2);
+
+        sm.process(&Stop).unwrap();
+        assert!(sm.is::<Locked>());
+        assert!(!sm.process(&Help).is_handled());
+    }
+
+    trait Cancel {
+        fn code(&self) -> u32;
+    }
+    struct Abort;
+    impl Cancel for Abort {
+        fn code(&self) -> u32 {
+            1
+        }
+    }
+    struct Timeout;
+    impl Cancel for Timeout {
+        fn code(&self) -> u32 {
+            2
+        }
+    }
+    crate::family!(dyn Cancel: Abort, Timeout);
+
+    fn is_abort(event: &dyn Cancel) -> Result<(), ()> {
+        if event.code() == 1 {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    fn cancel(_: &mut Unlocked, code: &mut u32, event: &dyn Cancel, _: &mut Locked) {
+        *code = event.code();
+    }
+
+    #[test]
+    fn test_family() {
+        let mut sm = state_machine!(
+            state = 0u32, err = (),
+            [Locked, Unlocked],
+
+            InitialPseudoState + ()                   => Locked,
+            Locked             + Push                 => Unlocked,
+            Unlocked           + dyn Cancel | cancel  => Locked;
+
+            forall:
+            except [Unlocked] + dyn Cancel [is_abort] => TerminationPseudoState;
+        );
+
+        sm.process(&()).unwrap();
+        sm.process(&Push).unwrap();
+        sm.process(&Timeout).unwrap();
+        assert!(sm.is::<Locked>());
+        assert_eq!(*sm.state(), 2);
+
+        assert!(!sm.process(&Stop).is_handled());
+        assert!(matches!(
+            sm.process(&Timeout),
+            crate::ProcessResult::GuardErr(_)
+        ));
+        sm.process(&Abort).unwrap();
+        assert!(sm.is::<TerminationPseudoState>());
+    }
+
+    fn unexpected(names: &mut Vec<&'static str>, event: &Unhandled) {
+        names.push(event.type_name());
+    }
+
+    #[test]
+    fn test_catch_all() {
+        let mut sm = state_machine!(
+            state = Vec::new(), err = (),
+            [Locked, Unlocked],
+
+            InitialPseudoState + ()   => Locked,
+            Locked             + Push => Unlocked,
+            Locked             + _    | unexpected,
+            Unlocked           + _    => Locked;
+
+            forall:
+            + Stop => TerminationPseudoState;
+        );
+
+        sm.process(&()).unwrap();
+        sm.process(&Kick).unwrap();
+        assert!(sm.is::<Locked>());
+        assert_eq!(*sm.state(), vec![std::any::type_name::<Kick>()]);
+
+        sm.process(&Push).unwrap();
+        sm.process(&Kick).unwrap();
+        assert!(sm.is::<Locked>());
+
+        sm.process(&Stop).unwrap();
+        assert!(sm.is::<TerminationPseudoState>());
+        assert!(!sm.process(&Kick).is_handled());
+    }
+
+    #[test]
+    fn test_catch_all_sub_state_machine() {
+        use crate::vertex::StateMachineVertex;
+
+        struct Inner;
+        struct Enter;
+        struct Finish;
+
+        let inner: StateMachineVertex<Inner, _, _, _> = StateMachineVertex::empty(state_machine!(
+            state = (), err = (),
+            [],
+
+            InitialPseudoState + Finish => TerminationPseudoState;
+        ));
+        #[rustfmt::skip]
+        let mut sm = state_machine!(
+            state = Vec::new(), err = (),
+            [Locked, @Sub inner],
+
+            InitialPseudoState                   + ()    => Locked,
+            Locked                               + Enter => StateMachineVertex<Inner, _, _, _>,
+            (StateMachineVertex<Inner, _, _, _>) + _     | unexpected;
+        );
+        sm.process(&()).unwrap();
+        sm.process(&Enter).unwrap();
+
+        assert_eq!(
+            sm.can_process(&Kick).unwrap().kind,
+            crate::process_result::TransitionKind::CatchAll
+        );
+        assert!(sm.explain(&Kick).result.is_ok());
+        sm.process(&Kick).unwrap();
+        assert_eq!(*sm.state(), vec![std::any::type_name::<Kick>()]);
+
+        sm.process(&Finish).unwrap();
+        assert_eq!(sm.state().len(), 1);
+    }
+
+    fn is_even(count: &u32) -> Result<(), ()> {
+        if count.is_multiple_of(2) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    #[test]
+    fn test_priority() {
+        let mut sm = state_machine!(
+            state = 0u32, err = (),
+            [Locked, Unlocked],
+
+            InitialPseudoState + ()     => Locked,
+            Locked             + Push   => Unlocked,
+            priority(1)
+            Locked             + u32 [is_even] => TerminationPseudoState;
+
+            forall:
+            priority(2) + Reset => Locked;
+            + Push => Locked;
+            + u32 => Unlocked;
+        );
+
+        sm.process(&()).unwrap();
+        sm.process(&Push).unwrap();
+        assert!(sm.is::<Unlocked>());
+
+        sm.process(&Reset).unwrap();
+        assert!(sm.is::<Locked>());
+
+        assert!(matches!(
+            sm.process(&1u32),
+            crate::ProcessResult::Handled(())
+        ));
+        assert!(sm.is::<Unlocked>());
+
+        sm.process(&Push).unwrap();
+        sm.process(&2u32).unwrap();
+        assert!(sm.is::<TerminationPseudoState>());
+    }
+
+    #[test]
+    fn test_conflict() {
+        let mut sm = state_machine!(
+            state = 0u32, err = (),
+            [Locked, Unlocked],
+
+            InitialPseudoState + ()   => Locked,
+            Locked             + Push => Unlocked,
+            Locked             + Push => TerminationPseudoState;
+        );
+
+        sm.process(&()).unwrap();
+        assert!(matches!(sm.process(&Push), crate::ProcessResult::Conflict));
+        assert!(sm.is::<Locked>());
+    }
+
+    #[test]
+    fn test_declaration_order() {
+        use crate::action::Prioritized;
+
+        // Transitions with the same rank used to be chosen in the order of declaration. Now they
+        // conflict, and the first one must be given a higher priority to win.
+        let mut sm = state_machine!(
+            state = 0u32, err = (),
+            [Locked, Unlocked],
+
+            InitialPseudoState + ()   => Locked,
+            priority(1)
+            Locked             + Push => Unlocked,
+            Locked             + Push => TerminationPseudoState,
+            Unlocked           + Push | Prioritized::new(1, || ()) => Locked,
+            Unlocked           + Push => TerminationPseudoState;
+        );
+
+        sm.process(&()).unwrap();
+        sm.process(&Push).unwrap();
+        assert!(sm.is::<Unlocked>());
+        sm.process(&Push).unwrap();
+        assert!(sm.is::<Locked>());
+    }
+
+    struct Typed<'a>(&'a str);
+    crate::borrowed_event!(Typed<'a>);
+
+    fn is_code(event: &Typed) -> Result<(), String> {
+        match event.0 == "1234" {
+            true => Ok(()),
+            false => Err(format!("wrong code {}", event.0)),
+        }
+    }
+
+    fn remember(_: &mut Locked, tries: &mut Vec<String>, event: &Typed, _: &mut Unlocked) {
+        tries.push(event.0.to_string());
+    }
+
+    #[test]
+    fn test_borrowed() {
+        let mut sm = state_machine!(
+            state = Vec::new(), err = String,
+            [Locked, Unlocked],
+
+            InitialPseudoState + ()                  => Locked,
+            Locked             + Typed<'_> [is_code] | remember => Unlocked;
+        );
+
+        sm.process(&()).unwrap();
+
+        let mut buffer = String::from("0000");
+        let result = sm.process_borrowed(&Typed(&buffer));
+        assert!(
+            matches!(result, crate::ProcessResult::GuardErr(e) if e.error == "wrong code 0000")
+        );
+        assert!(sm.is::<Locked>());
+
+        buffer.replace_range(.., "1234");
+        sm.process_borrowed(&Typed(&buffer)).unwrap();
+        assert!(sm.is::<Unlocked>());
+        assert_eq!(*sm.state(), ["1234"]);
+    }
+
+    #[test]
+    fn test_borrowed_by_process() {
+        let mut sm = state_machine!(
+            state = Vec::new(), err = String,
+            [Locked, Unlocked],
+
+            InitialPseudoState + ()                  => Locked,
+            Locked             + Typed<'_> [is_code] | remember => Unlocked;
+        );
+
+        sm.process(&()).unwrap();
+        assert!(matches!(
+            sm.process(&Typed("0000")),
+            crate::ProcessResult::GuardErr(_)
+        ));
+        assert!(sm.can_process(&Typed("1234")).is_ok());
+        sm.process(&Typed("1234")).unwrap();
+        assert!(sm.is::<Unlocked>());
+        assert_eq!(*sm.state(), ["1234"]);
+    }
+
+    struct Key(String);
+
+    fn is_right(key: &Key) -> Result<(), String> {
+        match key.0 == "right" {
+            true => Ok(()),
+            false => Err(format!("wrong key {}", key.0)),
+        }
+    }
+
+    fn open(_: &mut Locked, keys: &mut Vec<String>, key: Key, _: &mut Unlocked) {
+        keys.push(key.0);
+    }
+
+    #[test]
+    fn test_owned() {
+        use crate::ProcessOwnedEvent;
+
+        let mut sm = state_machine!(
+            state = Vec::new(), err = String,
+            [Locked, Unlocked],
+
+            InitialPseudoState + ()                  => Locked,
+            Locked             + move Key [is_right] | open => Unlocked,
+            Unlocked           + Key                 => Locked;
+        );
+
+        sm.process(&()).unwrap();
+        assert!(matches!(
+            sm.process(&Key("right".to_string())),
+            crate::ProcessResult::OwnedEventRequired
+        ));
+        assert_eq!(
+            sm.can_process(&Key("left".to_string())),
+            Err(crate::ProcessError::OwnedEventRequired)
+        );
+        assert!(sm.is::<Locked>());
+
+        let result = sm.process_owned(Key("left".to_string()));
+        assert!(matches!(result, crate::ProcessResult::GuardErr(e) if e.error == "wrong key left"));
+        assert!(sm.is::<Locked>());
+
+        sm.process_owned(Key("right".to_string())).unwrap();
+        assert!(sm.is::<Unlocked>());
+        assert_eq!(*sm.state(), ["right"]);
+
+        sm.process_owned(Key("any".to_string())).unwrap();
+        assert!(sm.is::<Locked>());
+    }
+
+    struct Login(&'static str);
+    struct Logout;
+    struct Session {
+        user: &'static str,
+        number: u32,
+    }
+    impl EntryVertex for Session {}
+    impl ExitVertex for Session {}
+
+    fn open_session(count: &mut u32, login: &Login) -> Session {
+        *count += 1;
+        Session {
+            user: login.0,
+            number: *count,
+        }
+    }
+
+    #[test]
+    fn test_on_entry() {
+        let mut sm = state_machine!(
+            state = 0, err = (),
+            [Locked, open_session => Session],
+
+            InitialPseudoState + ()     => Locked,
+            Locked             + Login  => Session,
+            Session            + Logout => Locked;
+        );
+
+        sm.process(&()).unwrap();
+        assert!(sm.get_vertex::<Session, _>().is_none());
+
+        sm.process(&Login("alice")).unwrap();
+        let session = sm.get_vertex::<Session, _>().unwrap();
+        assert_eq!((session.user, session.number), ("alice", 1));
+
+        sm.process(&Logout).unwrap();
+        assert!(sm.get_vertex::<Session, _>().is_none());
+
+        sm.process(&Login("bob")).unwrap();
+        let session = sm.get_vertex::<Session, _>().unwrap();
+        assert_eq!((session.user, session.number), ("bob", 2));
+    }
+
+    #[derive(Clone, Default)]
+    struct Attempts(u32);
+    impl EntryVertex for Attempts {}
+    impl ExitVertex for Attempts {}
+
+    struct Retry;
+
+    fn count_attempt(attempts: &mut Attempts, _: &mut (), _: &Retry) {
+        attempts.0 += 1;
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut sm = state_machine!(
+            state = (), err = (),
+            [Locked, reset: Default(Attempts)],
+
+            InitialPseudoState + ()   => Locked,
+            Locked             + Push => Attempts,
+            Attempts           + ()   => Locked;
+
+            loop: Attempts + Retry | count_attempt;
+        );
+        sm.process(&()).unwrap();
+        sm.process(&Push).unwrap();
+        sm.process(&Retry).unwrap();
+        sm.process(&Retry).unwrap();
+        assert_eq!(sm.get_vertex::<Attempts, _>().unwrap().0, 2);
+
+        sm.process(&()).unwrap();
+        assert_eq!(sm.get_vertex::<Attempts, _>().unwrap().0, 2);
+        sm.process(&Push).unwrap();
+        assert_eq!(sm.get_vertex::<Attempts, _>().unwrap().0, 0);
+
+        let mut sm = state_machine!(
+            state = (), err = (),
+            [Locked, reset: Clone(Attempts(5))],
+
+            InitialPseudoState + ()   => Locked,
+            Locked             + Push => Attempts,
+            Attempts           + ()   => Locked;
+
+            loop: Attempts + Retry | count_attempt;
+        );
+        sm.process(&()).unwrap();
+        sm.process(&Push).unwrap();
+        sm.process(&Retry).unwrap();
+        assert_eq!(sm.get_vertex::<Attempts, _>().unwrap().0, 6);
+        sm.process(&()).unwrap();
+        sm.process(&Push).unwrap();
+        assert_eq!(sm.get_vertex::<Attempts, _>().unwrap().0, 5);
+    }
+
+    #[test]
+    fn test_reset_sub_state_machine() {
+        use crate::vertex::StateMachineVertex;
+
+        struct Inner;
+        struct Enter;
+        struct Leave;
+        struct Finish;
+
+        let inner: StateMachineVertex<Inner, _, _, _> = StateMachineVertex::empty(state_machine!(
+            state = (), err = (),
+            [],
+
+            InitialPseudoState + Finish => TerminationPseudoState;
+        ));
+        let mut sm = state_machine!(
+            state = (), err = (),
+            [Locked, @Sub reset: Clone(inner)],
+
+            InitialPseudoState                    + ()    => Locked,
+            Locked                                + Enter => StateMachineVertex<Inner, _, _, _>,
+            (StateMachineVertex<Inner, _, _, _>)  + Leave => Locked;
+        );
+        sm.process(&()).unwrap();
+        sm.process(&Enter).unwrap();
+        assert_eq!(
+            sm.process_detailed(&Finish).kind,
+            Some(crate::process_result::TransitionKind::SubMachine)
+        );
+        sm.process(&Leave).unwrap();
+        assert!(sm.is::<Locked>());
+
+        sm.process(&Enter).unwrap();
+        let inner = sm
+            .get_vertex::<StateMachineVertex<Inner, _, _, _>, _>()
+            .unwrap();
+        assert!(inner.sm.is::<InitialPseudoState>());
+        assert!(!sm.process(&Leave).is_handled());
+        assert!(!sm.is::<Locked>());
+    }
+
+    fn greet(_: &mut InitialPseudoState, starts: &mut u32, _: &Start, _: &mut Locked) {
+        *starts += 1;
+    }
+
+    #[test]
+    fn test_start() {
+        let mut sm = state_machine!(
+            state = 0, err = (),
+            [Locked, Unlocked],
+
+            initial => Locked | greet;
+
+            Locked   + () => Unlocked,
+            Unlocked + () => Locked;
+        );
+        assert!(!sm.process(&()).is_handled());
+        assert!(sm.is::<InitialPseudoState>());
+
+        sm.start().unwrap();
+        assert!(sm.is::<Locked>());
+        assert!(!sm.start().is_handled());
+        assert_eq!(*sm.state(), 1);
+
+        sm.process(&()).unwrap();
+        assert!(sm.is::<Unlocked>());
+    }
+
+    fn kick(_: &mut Locked, kicks: &mut u32, _: &Kick) {
+        *kicks += 1;
+    }
+
+    fn report(kicks: &mut u32, _: &Stop) -> Final<String> {
+        Final(format!("{} kicks", kicks))
+    }
+
+    #[test]
+    fn test_finish() {
+        let mut sm = state_machine!(
+            state = 0, err = (),
+            [Locked, report => Final<String>],
+
+            initial => Locked;
+
+            Locked + Stop => Final<String>;
+
+            loop: Locked + Kick | kick;
+        );
+        sm.start().unwrap();
+        sm.process(&Kick).unwrap();
+        sm.process(&Kick).unwrap();
+
+        let sm = sm.finish::<Final<String>, _, _>().err().unwrap();
+        let mut sm = sm.into_parts().err().unwrap();
+        sm.process(&Stop).unwrap();
+        assert!(sm.is::<Final<String>>());
+        assert!(matches!(
+            sm.process(&Kick),
+            crate::ProcessResult::Terminated
+        ));
+
+        let (kicks, outcome) = sm.finish::<Final<String>, _, _>().ok().unwrap();
+        assert_eq!(kicks, 2);
+        assert_eq!(outcome.0, "2 kicks");
+    }
+
+    #[test]
+    fn test_final_sub_state_machine() {
+        use crate::vertex::StateMachineVertex;
+
+        struct Inner;
+        struct Enter;
+        struct Leave;
+        struct Finish;
+
+        let inner: StateMachineVertex<Inner, _, _, _> = StateMachineVertex::empty(state_machine!(
+            state = (), err = (),
+            [Final(())],
+
+            InitialPseudoState + Finish => Final<()>;
+        ));
+        let mut sm = state_machine!(
+            state = (), err = (),
+            [Locked, @Sub inner],
+
+            InitialPseudoState                    + ()    => Locked,
+            Locked                                + Enter => StateMachineVertex<Inner, _, _, _>,
+            (StateMachineVertex<Inner, _, _, _>)  + Leave => Locked;
+        );
+        sm.process(&()).unwrap();
+        sm.process(&Enter).unwrap();
+        assert!(sm.can_process(&Leave).is_err());
+
+        sm.process(&Finish).unwrap();
+        assert!(sm.is_in::<(Inner, Final<()>), _>());
+        assert!(sm.can_process(&Leave).is_ok());
+        sm.process(&Leave).unwrap();
+        assert!(sm.is::<Locked>());
+    }
+
+    fn never(_: &Kick) -> Result<(), String> {
+        Err("never".to_string())
+    }
+
+    #[test]
+    fn test_ignore_and_terminated() {
+        use crate::process_result::ProcessError;
+        use crate::ProcessResult;
+
+        let mut sm = state_machine!(
+            state = (), err = String,
+            [Locked, Unlocked],
+
+            initial => Locked;
+
+            Locked   + Push         => Unlocked,
+            Locked   + Kick [never] => Unlocked,
+            Unlocked + Stop         => TerminationPseudoState;
+
+            ignore:
+            Locked   + Kick;
+            Unlocked + (Kick | Reset);
+        );
+        assert!(matches!(sm.process(&Kick), ProcessResult::NoTransitions));
+        sm.start().unwrap();
+        assert!(matches!(sm.process(&Kick), ProcessResult::Ignored));
+        assert!(matches!(sm.process(&Stop), ProcessResult::NoTransitions));
+
+        sm.process(&Push).unwrap();
+        assert!(matches!(sm.process(&Reset), ProcessResult::Ignored));
+        assert_eq!(
+            sm.process(&Push).into_result(),
+            Err(ProcessError::NoTransitions)
+        );
+
+        let result: Result<(), Box<dyn std::error::Error>> = (|| {
+            sm.process(&Stop).into_result()?;
+            sm.process(&Push).into_result()?;
+            Ok(())
+        })();
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "state machine is already terminated"
+        );
+        assert!(matches!(sm.process(&Kick), ProcessResult::Terminated));
+    }
+
+    #[test]
+    fn test_defer() {
+        use crate::process_result::ProcessError;
+        use crate::ProcessResult;
+
+        let mut sm = state_machine!(
+            state = (), err = (),
+            [Locked, Unlocked],
+
+            initial => Locked;
+
+            Locked   + Push => Unlocked,
+            Unlocked + Kick => Locked;
+
+            defer:
+            Locked + (Kick | Reset);
+
+            ignore:
+            Locked + Reset;
+        );
+        sm.start().unwrap();
+        assert!(matches!(sm.process(&Kick), ProcessResult::Deferred));
+        assert!(matches!(sm.can_process(&Kick), Err(ProcessError::Deferred)));
+        assert!(matches!(sm.process(&Reset), ProcessResult::Ignored));
+        assert_eq!(
+            ProcessError::<String>::Deferred.to_string(),
+            "event is deferred by the current vertex"
+        );
+
+        // The state machine does not keep the deferred event. The caller must send it again
+        // after the vertex is changed.
+        sm.process(&Push).unwrap();
+        assert!(sm.is::<Unlocked>());
+        sm.process(&Kick).unwrap();
+        assert!(sm.is::<Locked>());
+    }
+
+    fn closed(_: &Push) -> Result<(), String> {
+        Err("closed".to_string())
+    }
+
+    #[test]
+    fn test_process_detailed() {
+        use crate::process_result::{GuardOutcome, TransitionKind};
+
+        let mut sm = state_machine!(
+            state = 0, err = String,
+            [Locked, Unlocked],
+
+            initial => Locked;
+
+            Locked   + Push          => Unlocked,
+            Unlocked + Push [closed] => Locked;
+
+            forall:
+            + Reset => Locked;
+
+            loop:
+            Locked + Kick | kick;
+        );
+        sm.start().unwrap();
+
+        let details = sm.process_detailed(&Kick);
+        assert!(details.result.is_handled());
+        assert!(details.event.ends_with("Kick"));
+        assert!(details.source.ends_with("Locked"));
+        assert!(details.target.ends_with("Locked"));
+        assert_eq!(details.kind, Some(TransitionKind::Loop));
+        assert_eq!(details.guard, GuardOutcome::Passed);
+
+        let details = sm.process_detailed(&Push);
+        assert!(details.source.ends_with("::Locked"));
+        assert!(details.target.ends_with("::Unlocked"));
+        assert_eq!(details.kind, Some(TransitionKind::Normal));
+
+        let details = sm.process_detailed(&Push);
+        assert!(matches!(details.result, crate::ProcessResult::GuardErr(e) if e.error == "closed"));
+        assert!(details.target.ends_with("::Unlocked"));
+        assert_eq!(details.kind, None);
+        assert_eq!(details.guard, GuardOutcome::Rejected);
+
+        let details = sm.process_detailed(&Reset);
+        assert!(details.target.ends_with("::Locked"));
+        assert_eq!(details.kind, Some(TransitionKind::Forall));
+
+        let details = sm.process_detailed(&Stop);
+        assert!(!details.result.is_handled());
+        assert_eq!(details.guard, GuardOutcome::NotChecked);
+    }
+
+    #[test]
+    fn test_empty_vertex_slot() {
+        use crate::utils::VertexSelector;
+        use crate::ProcessResult;
+
+        let mut sm = state_machine!(
+            state = (), err = String,
+            [Locked, Unlocked],
+
+            initial => Locked;
+
+            Locked + Push [closed] => TerminationPseudoState,
+            Locked + Push          => Unlocked;
+        );
+        sm.start().unwrap();
+
+        // The chosen transition cannot enter its target, and the next one is not tried.
+        VertexSelector::<Unlocked, _>::slot_mut(&mut sm.vertexes).take();
+        assert!(matches!(sm.process(&Push), ProcessResult::NoTransitions));
+        assert!(sm.is::<Locked>());
+
+        VertexSelector::<Locked, _>::slot_mut(&mut sm.vertexes).take();
+        assert!(sm.get_current().is_none());
+        assert!(matches!(sm.process(&Push), ProcessResult::NoTransitions));
+        assert!(sm.is::<Locked>());
+    }
+
+    struct Query;
+    impl crate::Request for Query {
+        type Answer = u32;
+    }
+    impl crate::Request for Push {
+        type Answer = ();
+    }
+    impl crate::Request for Key {
+        type Answer = String;
+    }
+    impl crate::Request for Kick {
+        type Answer = ();
+    }
+
+    fn count(_: &mut Locked, ctx: &mut u32, _: &Query) -> u32 {
+        *ctx
+    }
+
+    fn take(_: &mut Locked, keys: &mut u32, key: Key, _: &mut Unlocked) -> String {
+        *keys += 1;
+        key.0
+    }
+
+    #[test]
+    fn test_request() {
+        use crate::{ProcessOwnedRequest, ProcessRequest};
+
+        #[rustfmt::skip]
+        let mut sm = state_machine!(
+            state = 0u32, err = (), answer = Reply,
+            [Locked, Unlocked],
+
+            initial => Locked;
+            Locked   + move Key   | take  => Unlocked;
+            Unlocked + (Push | Kick)      => Locked;
+
+            loop:
+            Locked + Query | count;
+        );
+        sm.start().unwrap();
+
+        let answer: u32 = sm.request(&Query).unwrap();
+        assert_eq!(answer, 0);
+
+        let key: String = sm.request_owned(Key("key".to_string())).unwrap();
+        assert_eq!(key, "key");
+        sm.request(&Kick).unwrap();
+        assert_eq!(sm.request(&Query).unwrap(), 1);
+        assert!(matches!(
+            sm.request(&Push),
+            crate::ProcessResult::NoTransitions
+        ));
+    }
+
+    #[test]
+    fn test_guard_rejection() {
+        use crate::guard::Named;
+
+        #[rustfmt::skip]
+        let mut sm = state_machine!(
+            state = (), err = String,
+            [Locked, Unlocked],
+
+            initial => Locked;
+            Locked + Push [Named("always", |_: &Push| Ok::<(), &str>(())), closed] => Unlocked;
+        );
+        sm.start().unwrap();
+
+        let rejection = match sm.process(&Push) {
+            crate::ProcessResult::GuardErr(rejection) => rejection,
+            _ => panic!("expected GuardErr"),
+        };
+        assert_eq!(rejection.error, "closed");
+        assert_eq!(rejection.guard_index, 1);
+        assert!(rejection.guard_name.unwrap().ends_with("::closed"));
+        assert!(rejection.transition.source.ends_with("::Locked"));
+        assert!(rejection.transition.event.ends_with("::Push"));
+        assert!(rejection.transition.target.ends_with("::Unlocked"));
+        assert!(rejection.to_string().starts_with("closed (guard #1 `"));
+    }
+
+    #[test]
+    fn test_can_process() {
+        use crate::process_result::{ProcessError, TransitionKind};
+
+        #[rustfmt::skip]
+        let mut sm = state_machine!(
+            state = 0u32, err = String,
+            [Locked, Unlocked],
+
+            initial => Locked;
+            Locked   + Push           => Unlocked;
+            Unlocked + Push [closed]  => Locked;
+
+            forall:
+            + Reset => Locked;
+
+            loop:
+            Locked + Kick | kick;
+        );
+        assert!(sm.enabled_event_types()[0].ends_with("::Start"));
+        sm.start().unwrap();
+
+        let info = sm.can_process(&Push).unwrap();
+        assert!(info.transition.target.ends_with("::Unlocked"));
+        assert_eq!(info.kind, TransitionKind::Normal);
+        assert_eq!(sm.can_process(&Kick).unwrap().kind, TransitionKind::Loop);
+        assert_eq!(sm.can_process(&Stop), Err(ProcessError::NoTransitions));
+        assert!(matches!(
+            sm.can_process(&Reset),
+            Err(ProcessError::NoTransitions)
+        ));
+        assert!(sm.is::<Locked>());
+        assert_eq!(*sm.state(), 0);
+
+        let events = sm.enabled_event_types();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().any(|e| e.ends_with("::Push")));
+        assert!(events.iter().any(|e| e.ends_with("::Kick")));
+
+        sm.process(&Push).unwrap();
+        assert!(
+            matches!(sm.can_process(&Push), Err(ProcessError::GuardErr(e)) if e.error == "closed")
+        );
+        assert_eq!(sm.can_process(&Reset).unwrap().kind, TransitionKind::Forall);
+        let events = sm.enabled_event_types();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().any(|e| e.ends_with("::Reset")));
+    }
+
+    #[test]
+    fn test_explain() {
+        use crate::process_result::{CandidateOutcome, ProcessError};
+        use crate::transition::Kind;
+
+        #[rustfmt::skip]
+        let mut sm = state_machine!(
+            state = 0u32, err = String,
+            [Locked, Unlocked],
+
+            initial => Locked;
+            Locked   + Push           => Unlocked;
+            Unlocked + Push [closed]  => Locked;
+
+            forall:
+            + Reset => Locked;
+        );
+        sm.start().unwrap();
+        sm.process(&Push).unwrap();
+
+        let explanation = sm.explain(&Push);
+        assert!(explanation.event.ends_with("::Push"));
+        assert!(explanation.vertex.ends_with("::Unlocked"));
+        assert!(explanation.sub_machine.is_none());
+        assert!(
+            matches!(&explanation.result, Err(ProcessError::GuardErr(e)) if e.error == "closed")
+        );
+        assert_eq!(explanation.candidates.len(), 2);
+
+        let push = &explanation.candidates[0];
+        assert_eq!(push.kind, Kind::Specific);
+        assert!(push.transition.target.ends_with("::Locked"));
+        assert!(matches!(&push.outcome, CandidateOutcome::Rejected(e) if e.guard_index == 0));
+
+        let reset = &explanation.candidates[1];
+        assert_eq!(reset.kind, Kind::Forall);
+        assert_eq!(reset.outcome, CandidateOutcome::EventMismatch);
+
+        let explanation = sm.explain(&Stop);
+        assert_eq!(explanation.result, Err(ProcessError::NoTransitions));
+        assert!(explanation
+            .candidates
+            .iter()
+            .all(|c| c.outcome == CandidateOutcome::EventMismatch));
+        assert!(sm.is::<Unlocked>());
+    }
+
+    #[test]
+    fn test_describe() {
+        use crate::process_result::TransitionKind;
+        use crate::vertex::StateMachineVertex;
+        use std::any::type_name;
+
+        struct Inner;
+
+        let inner: StateMachineVertex<Inner, _, _, _> = StateMachineVertex::empty(state_machine!(
+            state = (), err = (),
+            [],
+
+            InitialPseudoState + Stop => TerminationPseudoState;
+        ));
+        #[rustfmt::skip]
+        let sm = state_machine!(
+            state = 0u32, err = String,
+            [Locked, Unlocked, @Sub inner],
+
+            initial => Locked;
+            Locked   + Push [closed]  => Unlocked;
+            Unlocked + Push           => StateMachineVertex<Inner, _, _, _>;
+
+            forall:
+            + Reset => Locked;
+
+            loop:
+            Locked + Kick | kick;
+        );
+        let description = sm.describe();
+        assert_eq!(description.vertices.len(), 5);
+
+        let sub = description
+            .vertices
+            .iter()
+            .find_map(|v| v.sub_machine.as_ref())
+            .unwrap();
+        assert_eq!(sub.vertices.len(), 2);
+        assert_eq!(sub.transitions.len(), 1);
+        assert_eq!(
+            sub.transitions[0].target,
+            Some(type_name::<TerminationPseudoState>())
+        );
+        assert!(description
+            .vertex(type_name::<Locked>())
+            .unwrap()
+            .sub_machine
+            .is_none());
+
+        let from_locked: Vec<_> = description
+            .transitions_from(type_name::<Locked>())
+            .collect();
+        assert_eq!(from_locked.len(), 2);
+        let push = from_locked
+            .iter()
+            .find(|t| t.kind == TransitionKind::Normal)
+            .unwrap();
+        assert_eq!(push.events, vec![type_name::<Push>()]);
+        assert_eq!(push.target, Some(type_name::<Unlocked>()));
+        assert_eq!(push.guards, 1);
+        assert_eq!(push.action, None);
+        let kick = from_locked
+            .iter()
+            .find(|t| t.kind == TransitionKind::Loop)
+            .unwrap();
+        assert_eq!(kick.guards, 0);
+        assert!(kick.action.unwrap().ends_with("::kick"));
+
+        let reset = description
+            .transitions
+            .iter()
+            .find(|t| t.kind == TransitionKind::Forall)
+            .unwrap();
+        assert_eq!(reset.source, None);
+        assert!(reset.starts_from(type_name::<Unlocked>()));
+        assert!(!reset.starts_from(type_name::<Locked>()));
+    }
+
+    #[test]
+    fn test_active_configuration() {
+        use crate::vertex::StateMachineVertex;
+        use std::any::type_name;
+
+        struct Inner;
+        struct Enter;
+
+        let inner: StateMachineVertex<Inner, _, _, _> = StateMachineVertex::empty(state_machine!(
+            state = (), err = (),
+            [Locked],
+
+            InitialPseudoState + Push => Locked;
+        ));
+        let mut sm = state_machine!(
+            state = (), err = (),
+            [Locked, @Sub inner],
+
+            InitialPseudoState + ()    => Locked,
+            Locked             + Enter => StateMachineVertex<Inner, _, _, _>;
+        );
+        assert_eq!(sm.current_name(), type_name::<InitialPseudoState>());
+        assert_eq!(
+            sm.active_configuration(),
+            vec![type_name::<InitialPseudoState>()]
+        );
+
+        sm.process(&()).unwrap();
+        sm.process(&Enter).unwrap();
+        assert_eq!(sm.current_name(), type_name::<Inner>());
+        assert_eq!(
+            sm.active_configuration(),
+            vec![type_name::<Inner>(), type_name::<InitialPseudoState>()]
+        );
+
+        sm.process(&Push).unwrap();
+        assert_eq!(
+            sm.active_configuration(),
+            vec![type_name::<Inner>(), type_name::<Locked>()]
+        );
+    }
+
+    #[test]
+    fn test_is_in() {
+        use crate::vertex::StateMachineVertex;
+
+        struct Inner;
+        struct Enter;
+
+        let mut inner: StateMachineVertex<Inner, _, _, _> =
+            StateMachineVertex::empty(state_machine!(
+                state = 0, err = (),
+                [Locked],
+
+                InitialPseudoState + Push => Locked;
+            ));
+        *inner.sm_mut().state_mut() = 1;
+        let mut sm = state_machine!(
+            state = (), err = (),
+            [Locked, @Sub inner],
+
+            InitialPseudoState + ()    => Locked,
+            Locked             + Enter => StateMachineVertex<Inner, _, _, _>;
+        );
+        sm.process(&()).unwrap();
+        assert!(sm.is_in::<(Locked,), _>());
+        assert!(!sm.is_in::<(Inner,), _>());
+        assert!(!sm.is_in::<(Inner, InitialPseudoState), _>());
+
+        sm.process(&Enter).unwrap();
+        assert!(sm.is_in::<(Inner,), _>());
+        assert!(sm.is_in::<(Inner, InitialPseudoState), _>());
+        assert!(!sm.is_in::<(Inner, Locked), _>());
+
+        sm.process(&Push).unwrap();
+        assert!(sm.is_in::<(Inner, Locked), _>());
+        assert!(!sm.is_in::<(Locked,), _>());
+
+        let inner = sm
+            .get_vertex::<StateMachineVertex<Inner, _, _, _>, _>()
+            .unwrap();
+        assert!(inner.sm().is::<Locked>());
+        assert_eq!(*inner.sm().state(), 1);
+    }
+
+    #[test]
+    fn test_with_current() {
+        trait Render {
+            fn render(&self) -> String;
+        }
+        impl Render for Locked {
+            fn render(&self) -> String {
+                "locked".to_string()
+            }
+        }
+        impl Render for Attempts {
+            fn render(&self) -> String {
+                format!("{} attempts", self.0)
+            }
+        }
+        crate::family!(dyn Render: Locked, Attempts);
+
+        struct Retry;
+
+        fn retry(attempts: &mut Attempts, _: &mut (), _: &Retry) {
+            attempts.0 += 1;
+        }
+
+        let mut sm = state_machine!(
+            state = (), err = (),
+            [Locked, Attempts(0)],
+
+            InitialPseudoState + ()   => Locked,
+            Locked             + Push => Attempts;
+
+            loop: Attempts + Retry | retry;
+        );
+        assert_eq!(sm.with_current::<dyn Render, _>(|v| v.render()), None);
+        sm.process(&()).unwrap();
+        assert_eq!(
+            sm.with_current::<dyn Render, _>(|v| v.render()),
+            Some("locked".to_string())
+        );
+        sm.process(&Push).unwrap();
+        sm.process(&Retry).unwrap();
+        assert_eq!(
+            sm.with_current::<dyn Render, _>(|v| v.render()),
+            Some("1 attempts".to_string())
+        );
+
+        let render = |v: &mut dyn Render| v.render();
+        assert_eq!(
+            sm.with_current_mut::<dyn Render, _>(render),
+            Some("1 attempts".to_string())
+        );
+    }
+
+    #[test]
+    fn test_accessors() {
+        use frunk::Coproduct;
+
+        let mut sm = state_machine!(
+            state = 0, err = (),
+            [Locked, Attempts(0)],
+
+            InitialPseudoState + ()   => Locked,
+            Locked             + Push => Attempts;
+        );
+        *sm.state_mut() += 1;
+        assert_eq!(*sm.state(), 1);
+
+        sm.get_vertex_mut::<Attempts, _>().unwrap().0 = 2;
+        assert_eq!(sm.get_vertex::<Attempts, _>().unwrap().0, 2);
+        sm.process(&()).unwrap();
+        assert!(sm.get_current_as_mut::<Attempts, _>().is_none());
+
+        sm.process(&Push).unwrap();
+        sm.get_current_as_mut::<Attempts, _>().unwrap().0 += 1;
+        if let Some(Coproduct::Inl(attempts)) = sm.get_current_mut() {
+            attempts.0 += 1;
+        }
+        assert_eq!(sm.get_current_as::<Attempts, _>().unwrap().0, 4);
+    }
 }
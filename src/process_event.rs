@@ -4,7 +4,108 @@
 //! cannot process an event, this is a bug.
 //!
 //! `ProcessEvent::process` returns an `ProcessResult`.
+//!
+//! All transitions of a `StateMachine` return the same `Answer`. A state machine declared with
+//! `answer = Reply` lets every transition return the answer type tied to its event by `Request`
+//! instead, and `ProcessRequest::request` returns the answer with this type:
+//!
+//! ```rust
+//! use umlsm::vertex::{EntryVertex, ExitVertex};
+//! use umlsm::{ProcessRequest, Request};
+//!
+//! struct Idle;
+//! impl EntryVertex for Idle {}
+//! impl ExitVertex for Idle {}
+//! struct Query;
+//! struct Command;
+//!
+//! impl Request for Query {
+//!     type Answer = u32;
+//! }
+//!
+//! impl Request for Command {
+//!     type Answer = ();
+//! }
+//!
+//! #[rustfmt::skip]
+//! let mut sm = umlsm::state_machine!(
+//!     state = 0u32, err = (), answer = Reply,
+//!     [Idle],
+//!
+//!     initial => Idle;
+//!
+//!     loop:
+//!     Idle + Query | |_: &mut Idle, ctx: &mut u32, _: &Query| *ctx;
+//!     Idle + Command | |_: &mut Idle, ctx: &mut u32, _: &Command| *ctx += 1;
+//! );
+//! sm.start().unwrap();
+//!
+//! sm.request(&Command).unwrap();
+//! let answer: u32 = sm.request(&Query).unwrap();
+//! assert_eq!(answer, 1);
+//! ```
+//!
+//! An action that returns another type than the answer of its event does not compile:
+//!
+//! ```compile_fail
+//! use umlsm::vertex::{EntryVertex, ExitVertex};
+//! use umlsm::Request;
+//!
+//! struct Idle;
+//! impl EntryVertex for Idle {}
+//! impl ExitVertex for Idle {}
+//! struct Query;
+//!
+//! impl Request for Query {
+//!     type Answer = u32;
+//! }
+//!
+//! #[rustfmt::skip]
+//! let mut sm = umlsm::state_machine!(
+//!     state = (), err = (), answer = Reply,
+//!     [Idle],
+//!
+//!     initial => Idle;
+//!
+//!     loop:
+//!     Idle + Query | |_: &mut Idle, _: &mut (), _: &Query| "not a number";
+//! );
+//! sm.start().unwrap();
+//! ```
+//!
+//! Nor does an action wrapped into `Replying` for another event than the trigger of its transition,
+//! which `state_machine!` never does, but `StateMachine::add_transition` could:
+//!
+//! ```compile_fail
+//! use umlsm::action::{Action, Reply, Replying};
+//! use umlsm::Request;
+//!
+//! struct Idle;
+//! struct Query;
+//! struct Command;
+//!
+//! impl Request for Query {
+//!     type Answer = u32;
+//! }
+//!
+//! impl Request for Command {
+//!     type Answer = ();
+//! }
+//!
+//! fn reply_to_command<A: Action<Idle, (), Command, Idle, Reply>>(_: A) {}
+//!
+//! reply_to_command(Replying::<_, Query>::new(
+//!     |_: &mut Idle, _: &mut (), _: &Command, _: &mut Idle| 0u32,
+//! ));
+//! ```
+//!
+//! Events of reply transitions are matched by type, family, borrowed and catch-all triggers are
+//! not supported in this mode. Events processed by value are answered by
+//! `ProcessOwnedRequest::request_owned`.
 
+use crate::action::Reply;
+use crate::process_result::{Explanation, ProcessDetails, ProcessError, TransitionInfo};
+use crate::trigger::{EventKind, Static};
 use crate::ProcessResult;
 
 /// An interface for processing events.
@@ -13,3 +114,93 @@ use crate::ProcessResult;
 pub trait ProcessEvent<E, Answer, GErr, Other> {
     fn process(&mut self, event: &E) -> ProcessResult<Answer, GErr>;
 }
+
+/// An event whose answer has a known type. Used by state machines declared with `answer = Reply`,
+/// all actions of transitions triggered by the event return `Answer`.
+pub trait Request: 'static {
+    type Answer: 'static;
+}
+
+/// The event of transitions from `InitialPseudoState` written as `InitialPseudoState + ()`.
+impl Request for () {
+    type Answer = ();
+}
+
+/// An interface for processing events by state machines declared with `answer = Reply`, that
+/// returns the answer with the type of `Request::Answer`.
+pub trait ProcessRequest<E: Request, GErr, Other> {
+    fn request(&mut self, event: &E) -> ProcessResult<E::Answer, GErr>;
+}
+
+/// An interface for processing events by value by state machines declared with `answer = Reply`,
+/// that returns the answer with the type of `Request::Answer`. See `ProcessOwnedEvent`.
+pub trait ProcessOwnedRequest<E: Request, GErr, Other> {
+    fn request_owned(&mut self, event: E) -> ProcessResult<E::Answer, GErr>;
+}
+
+/// An interface for processing events by their `EventKind`. Events of any kind, including ones
+/// that borrow data, can be processed by it.
+///
+/// `ProcessEvent` is implemented for all types that implement `ProcessEventKind` for `'static`
+/// events.
+pub trait ProcessEventKind<K: EventKind, Answer, GErr, Other> {
+    fn process_kind<'e>(&mut self, event: &'e K::Of<'e>) -> ProcessResult<Answer, GErr>;
+
+    /// Check whether the event would be handled, without calling actions or changing the state.
+    fn can_process_kind<'e>(
+        &self,
+        event: &'e K::Of<'e>,
+    ) -> Result<TransitionInfo, ProcessError<GErr>>;
+
+    /// Explain how a transition would be chosen for the event.
+    fn explain_kind<'e>(&self, event: &'e K::Of<'e>) -> Explanation<GErr>
+    where
+        GErr: Clone;
+}
+
+/// An interface for processing events by their `EventKind`, that returns `ProcessDetails` with a
+/// description of the fired transition.
+pub trait ProcessEventDetailed<K: EventKind, Answer, GErr, Other> {
+    fn process_kind_detailed<'e>(&mut self, event: &'e K::Of<'e>) -> ProcessDetails<Answer, GErr>;
+}
+
+/// An interface for processing events by value. The event can be moved into a vertex or the
+/// context by a transition with an owned action.
+///
+/// Transitions with owned actions are chosen only by `process_owned`, other transitions receive a
+/// reference to the event as usual. If a transition with an owned action accepts an event processed
+/// by reference, `ProcessResult::OwnedEventRequired` is returned. A sub state machine receives the
+/// event by reference.
+pub trait ProcessOwnedEvent<E, Answer, GErr, Other> {
+    fn process_owned(&mut self, event: E) -> ProcessResult<Answer, GErr>;
+}
+
+impl<T, E, Answer, GErr, Other> ProcessEvent<E, Answer, GErr, Other> for T
+where
+    T: ProcessEventKind<Static<E>, Answer, GErr, Other>,
+    E: 'static,
+{
+    fn process(&mut self, event: &E) -> ProcessResult<Answer, GErr> {
+        self.process_kind(event)
+    }
+}
+
+impl<T, E, GErr, Other> ProcessRequest<E, GErr, Other> for T
+where
+    T: ProcessEvent<E, Reply, GErr, Other>,
+    E: Request,
+{
+    fn request(&mut self, event: &E) -> ProcessResult<E::Answer, GErr> {
+        self.process(event).map(Reply::into_answer::<E>)
+    }
+}
+
+impl<T, E, GErr, Other> ProcessOwnedRequest<E, GErr, Other> for T
+where
+    T: ProcessOwnedEvent<E, Reply, GErr, Other>,
+    E: Request,
+{
+    fn request_owned(&mut self, event: E) -> ProcessResult<E::Answer, GErr> {
+        self.process_owned(event).map(Reply::into_answer::<E>)
+    }
+}
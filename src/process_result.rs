@@ -1,23 +1,63 @@
 //! Results that are returned from different interfaces.
 
+use crate::transition::Kind;
+use std::error::Error;
+use std::fmt;
+
 /// An result of processing event.
 ///
 /// - `Handled` - event handled and `Answer` is returned.
+/// - `Terminated` - event not handled because the state machine is already in a final vertex,
+///   `Final<Outcome>` or `TerminationPseudoState`.
+/// - `Ignored` - event not handled because the current vertex ignores it explicitly, by the
+///   `ignore:` section of `state_machine!`.
+/// - `Deferred` - event not handled because the current vertex defers it explicitly, by the `defer:`
+///   section of `state_machine!`. The state machine does not keep the event: the caller keeps it
+///   and processes it again after the current vertex is changed.
 /// - `NoTransitions` - event not handled because there are no transitions from this `Source` vertex
-/// that give a specified `Event` type.
-/// - `GuardErr` - event not handled because `Guard` not accept it and guard error returns.
+///   that give a specified `Event` type.
+/// - `GuardErr` - event not handled because `Guard` not accept it. `GuardRejection` tells which
+///   guard of which transition returns the error.
+/// - `Conflict` - event not handled because several transitions with the same priority accept it.
+/// - `OwnedEventRequired` - event not handled because the transition that accepts it takes the event
+///   by value (`move` in `state_machine!`), so the event must be processed by `process_owned`.
+///
+/// It can be converted to `Result<Answer, ProcessError<GErr>>` by `into_result` for use with `?`.
 pub enum ProcessResult<Answer, GErr> {
     Handled(Answer),
+    Terminated,
+    Ignored,
+    Deferred,
     NoTransitions,
-    GuardErr(GErr),
+    GuardErr(GuardRejection<GErr>),
+    Conflict,
+    OwnedEventRequired,
 }
 
 impl<Answer, GErr> ProcessResult<Answer, GErr> {
     pub fn ok(self) -> Option<Answer> {
         match self {
             ProcessResult::Handled(h) => Some(h),
+            ProcessResult::Terminated => None,
+            ProcessResult::Ignored => None,
+            ProcessResult::Deferred => None,
             ProcessResult::NoTransitions => None,
             ProcessResult::GuardErr(_) => None,
+            ProcessResult::Conflict => None,
+            ProcessResult::OwnedEventRequired => None,
+        }
+    }
+
+    pub fn into_result(self) -> Result<Answer, ProcessError<GErr>> {
+        match self {
+            ProcessResult::Handled(h) => Ok(h),
+            ProcessResult::Terminated => Err(ProcessError::Terminated),
+            ProcessResult::Ignored => Err(ProcessError::Ignored),
+            ProcessResult::Deferred => Err(ProcessError::Deferred),
+            ProcessResult::NoTransitions => Err(ProcessError::NoTransitions),
+            ProcessResult::GuardErr(e) => Err(ProcessError::GuardErr(e)),
+            ProcessResult::Conflict => Err(ProcessError::Conflict),
+            ProcessResult::OwnedEventRequired => Err(ProcessError::OwnedEventRequired),
         }
     }
 
@@ -26,15 +66,236 @@ impl<Answer, GErr> ProcessResult<Answer, GErr> {
 
         match self {
             Handled(a) => a,
+            Terminated => unreachable!("Expected handled result, found `Terminated`"),
+            Ignored => unreachable!("Expected handled result, found `Ignored`"),
+            Deferred => unreachable!("Expected handled result, found `Deferred`"),
             NoTransitions => unreachable!("Expected handled result, found `NoTransitions`"),
             GuardErr(_) => unreachable!("Expected handled result, found `GuardReturnFalse`"),
+            Conflict => unreachable!("Expected handled result, found `Conflict`"),
+            OwnedEventRequired => {
+                unreachable!("Expected handled result, found `OwnedEventRequired`")
+            }
         }
     }
 
     pub fn is_handled(&self) -> bool {
-        match &self {
-            ProcessResult::Handled(_) => true,
-            _ => false,
+        matches!(self, ProcessResult::Handled(_))
+    }
+
+    pub fn map<ANew>(self, f: impl FnOnce(Answer) -> ANew) -> ProcessResult<ANew, GErr> {
+        use ProcessResult::*;
+
+        match self {
+            Handled(a) => Handled(f(a)),
+            Terminated => Terminated,
+            Ignored => Ignored,
+            Deferred => Deferred,
+            NoTransitions => NoTransitions,
+            GuardErr(e) => GuardErr(e),
+            Conflict => Conflict,
+            OwnedEventRequired => OwnedEventRequired,
+        }
+    }
+}
+
+impl<Answer, GErr> From<ProcessResult<Answer, GErr>> for Result<Answer, ProcessError<GErr>> {
+    fn from(result: ProcessResult<Answer, GErr>) -> Self {
+        result.into_result()
+    }
+}
+
+impl<Answer, GErr> From<ProcessError<GErr>> for ProcessResult<Answer, GErr> {
+    fn from(error: ProcessError<GErr>) -> Self {
+        match error {
+            ProcessError::Terminated => ProcessResult::Terminated,
+            ProcessError::Ignored => ProcessResult::Ignored,
+            ProcessError::Deferred => ProcessResult::Deferred,
+            ProcessError::NoTransitions => ProcessResult::NoTransitions,
+            ProcessError::GuardErr(e) => ProcessResult::GuardErr(e),
+            ProcessError::Conflict => ProcessResult::Conflict,
+            ProcessError::OwnedEventRequired => ProcessResult::OwnedEventRequired,
+        }
+    }
+}
+
+/// An error of processing event, which is the not handled part of `ProcessResult`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcessError<GErr> {
+    Terminated,
+    Ignored,
+    Deferred,
+    NoTransitions,
+    GuardErr(GuardRejection<GErr>),
+    Conflict,
+    OwnedEventRequired,
+}
+
+impl<GErr: fmt::Display> fmt::Display for ProcessError<GErr> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessError::Terminated => write!(f, "state machine is already terminated"),
+            ProcessError::Ignored => write!(f, "event is ignored by the current vertex"),
+            ProcessError::Deferred => write!(f, "event is deferred by the current vertex"),
+            ProcessError::NoTransitions => write!(f, "no transitions for the event"),
+            ProcessError::GuardErr(e) => write!(f, "event is rejected by guard: {}", e),
+            ProcessError::Conflict => write!(f, "several transitions accept the event"),
+            ProcessError::OwnedEventRequired => {
+                write!(f, "the event must be processed by value")
+            }
+        }
+    }
+}
+
+impl<GErr: fmt::Debug + fmt::Display> Error for ProcessError<GErr> {}
+
+/// A diagnostic of the event rejected by guards.
+///
+/// - `transition` - the rejected transition.
+/// - `guard_index` - the index of the guard that returns the error in the list of guards.
+/// - `guard_name` - the name of the guard, see `Guard::name`.
+/// - `error` - the error returned by the guard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuardRejection<GErr> {
+    pub transition: TransitionName,
+    pub guard_index: usize,
+    pub guard_name: Option<&'static str>,
+    pub error: GErr,
+}
+
+impl<GErr: fmt::Display> fmt::Display for GuardRejection<GErr> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (guard #{}", self.error, self.guard_index)?;
+        if let Some(name) = self.guard_name {
+            write!(f, " `{}`", name)?;
+        }
+        write!(f, " of `{}`)", self.transition)
+    }
+}
+
+/// Type names of the source vertex, the event and the target vertex of a transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitionName {
+    pub source: &'static str,
+    pub event: &'static str,
+    pub target: &'static str,
+}
+
+impl TransitionName {
+    pub fn of<Source: ?Sized, Event: ?Sized, Target: ?Sized>() -> Self {
+        TransitionName {
+            source: std::any::type_name::<Source>(),
+            event: std::any::type_name::<Event>(),
+            target: std::any::type_name::<Target>(),
+        }
+    }
+}
+
+impl fmt::Display for TransitionName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} + {} => {}", self.source, self.event, self.target)
+    }
+}
+
+/// The transition that would be fired for the event, returned by `StateMachine::can_process`.
+///
+/// If the event would be handled by the sub state machine of the current vertex, `kind` is
+/// `SubMachine` and `transition` is the transition of the sub state machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitionInfo {
+    pub transition: TransitionName,
+    pub kind: TransitionKind,
+}
+
+/// A trace of choosing a transition for the event, returned by `StateMachine::explain`.
+///
+/// - `event` - the type name of the event.
+/// - `vertex` - the type name of the current vertex.
+/// - `sub_machine` - the result of the sub state machine of the current vertex, `None` if the
+///   vertex is not a sub state machine or it is terminated. If it is `Some`, transitions of this
+///   state machine are not checked.
+/// - `candidates` - all transitions that are checked, in the order of checking.
+/// - `result` - the transition that would be fired or the reason why the event is not handled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation<GErr> {
+    pub event: &'static str,
+    pub vertex: &'static str,
+    pub sub_machine: Option<Result<TransitionInfo, ProcessError<GErr>>>,
+    pub candidates: Vec<Candidate<GErr>>,
+    pub result: Result<TransitionInfo, ProcessError<GErr>>,
+}
+
+/// A transition that is checked for the event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate<GErr> {
+    pub transition: TransitionName,
+    pub kind: Kind,
+    pub priority: i32,
+    pub outcome: CandidateOutcome<GErr>,
+}
+
+/// Outcome of checking a transition.
+///
+/// - `EventMismatch` - the trigger does not accept the event.
+/// - `Accepted` - the trigger and guards accept the event.
+/// - `Rejected` - the trigger accepts the event, but a guard rejects it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CandidateOutcome<GErr> {
+    EventMismatch,
+    Accepted,
+    Rejected(GuardRejection<GErr>),
+}
+
+/// A result of `StateMachine::process_detailed`: `ProcessResult` with a description of what has
+/// happened, e.g. for audit logs.
+///
+/// - `event` - the type name of the event.
+/// - `source` - the type name of the vertex before processing.
+/// - `target` - the type name of the vertex after processing.
+/// - `kind` - the kind of the fired transition, `None` if the event is not handled.
+/// - `guard` - the outcome of guards.
+pub struct ProcessDetails<Answer, GErr> {
+    pub result: ProcessResult<Answer, GErr>,
+    pub event: &'static str,
+    pub source: &'static str,
+    pub target: &'static str,
+    pub kind: Option<TransitionKind>,
+    pub guard: GuardOutcome,
+}
+
+/// Kind of the fired transition.
+///
+/// - `Normal` - a transition from the current vertex to another one.
+/// - `Loop` - a loop of the current vertex.
+/// - `Forall` - a forall transition, including internal ones.
+/// - `CatchAll` - a catch-all transition (`Source + _`).
+/// - `SubMachine` - the event is handled by the sub state machine of the current vertex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionKind {
+    Normal,
+    Loop,
+    Forall,
+    CatchAll,
+    SubMachine,
+}
+
+/// Outcome of guards for the processed event.
+///
+/// - `Passed` - guards of the fired transition accept the event.
+/// - `Rejected` - no transition is fired because guards reject the event.
+/// - `NotChecked` - no guard accepts or rejects the event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardOutcome {
+    Passed,
+    Rejected,
+    NotChecked,
+}
+
+impl GuardOutcome {
+    pub fn of<Answer, GErr>(result: &ProcessResult<Answer, GErr>) -> Self {
+        match result {
+            ProcessResult::Handled(_) | ProcessResult::Conflict => GuardOutcome::Passed,
+            ProcessResult::GuardErr(_) => GuardOutcome::Rejected,
+            _ => GuardOutcome::NotChecked,
         }
     }
 }
@@ -42,31 +303,34 @@ impl<Answer, GErr> ProcessResult<Answer, GErr> {
 /// An inner result of processing event. It is need only if you implement your own `ITransition`.
 ///
 /// - `HandledAndProcessNext` - event handled, but answer is not returned because there are required
-/// at least one more step int `StateMachine`.
+///   at least one more step int `StateMachine`.
 /// - `EventTypeNotSatisfy` - event type that received not satisfy for type of `ITransition`.
 /// - `HandledAndProcessEnd` - event handled and `Answer` is returned.
 /// - `NoTransitions` - event not handled because there are no transitions from this `Source` vertex
-/// that give a specified `Event` type.
+///   that give a specified `Event` type.
 /// - `GuardErr` - event not handled because `Guard` not accept it and guard error returns.
+/// - `Conflict` - event not handled because several transitions with the same priority accept it.
 pub enum ProcessResultInner<Answer, GErr> {
     HandledAndProcessNext,
     EventTypeNotSatisfy,
     HandledAndProcessEnd(Answer),
     NoTransitions,
 
-    GuardErr(GErr),
+    GuardErr(GuardRejection<GErr>),
+    Conflict,
 }
 
-impl<Answer, GErr> Into<ProcessResult<Answer, GErr>> for ProcessResultInner<Answer, GErr> {
-    fn into(self) -> ProcessResult<Answer, GErr> {
+impl<Answer, GErr> From<ProcessResultInner<Answer, GErr>> for ProcessResult<Answer, GErr> {
+    fn from(inner: ProcessResultInner<Answer, GErr>) -> Self {
         use ProcessResultInner::*;
 
-        match self {
+        match inner {
             HandledAndProcessNext => unreachable!(),
             HandledAndProcessEnd(a) => ProcessResult::Handled(a),
             NoTransitions => ProcessResult::NoTransitions,
             GuardErr(e) => ProcessResult::GuardErr(e),
             EventTypeNotSatisfy => ProcessResult::NoTransitions,
+            Conflict => ProcessResult::Conflict,
         }
     }
 }
@@ -81,6 +345,7 @@ impl<A, GErr> ProcessResultInner<A, GErr> {
             NoTransitions => NoTransitions,
             GuardErr(e) => GuardErr(e),
             EventTypeNotSatisfy => ProcessResultInner::EventTypeNotSatisfy,
+            Conflict => Conflict,
         }
     }
 }
@@ -88,13 +353,22 @@ impl<A, GErr> ProcessResultInner<A, GErr> {
 /// An inner result of processing event by substate.
 ///
 /// - `Handled` - event handled and `Answer` is returned.
+/// - `Ignored` - event not handled because the current vertex of substate ignores it explicitly.
+/// - `Deferred` - event not handled because the current vertex of substate defers it explicitly.
 /// - `NoTransitions` - event not handled because there are no transitions from this `Source` vertex
-/// that give a specified `Event` type.
+///   that give a specified `Event` type.
 /// - `GuardErr` - event not handled because `Guard` not accept it and guard error returns.
+/// - `Conflict` - event not handled because several transitions with the same priority accept it.
+/// - `OwnedEventRequired` - event not handled because the transition that accepts it takes the event
+///   by value.
 /// - `MustLeaveState` - state machine must leave substate and make transition to another vertex.
 pub enum ProcessResultSubstate<Answer, GErr> {
     Handled(Answer),
+    Ignored,
+    Deferred,
     NoTransitions,
-    GuardErr(GErr),
+    GuardErr(GuardRejection<GErr>),
+    Conflict,
+    OwnedEventRequired,
     MustLeaveState,
 }
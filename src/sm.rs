@@ -10,24 +10,68 @@
 //! - `InitialPseudoState`
 //! - `TerminationPseudoState`
 //!
+//! The state machine leaves `InitialPseudoState` by the initial transition, which is written as
+//! `initial => Target | action` in `state_machine!` and is run by `StateMachine::start`.
+//!
 //! For initializing the `StateMachine` we recommend use the `state_machine!` macro.
+//!
+//! `StateMachine::is_in` checks the current vertices of the state machine and of its sub state
+//! machines by a path of vertex types. The path is checked at compile time, so a path through a
+//! vertex that is not a sub state machine does not compile:
+//!
+//! ```compile_fail
+//! use umlsm::vertex::{EntryVertex, ExitVertex};
+//!
+//! struct Idle;
+//! impl EntryVertex for Idle {}
+//! impl ExitVertex for Idle {}
+//!
+//! let mut sm = umlsm::state_machine!(
+//!     state = (), err = (),
+//!     [Idle],
+//!
+//!     initial => Idle;
+//! );
+//! sm.start().unwrap();
+//! assert!(sm.is_in::<(Idle,), _>());
+//! assert!(!sm.is_in::<(Idle, Idle), _>());
+//! ```
 
-use crate::action::{Action, ActionLoop, FnIntoStruct};
+use crate::action::{
+    Action, ActionLoop, FnIntoStruct, ForallAction, InternalAction, OwnedAction, OwnedActionLoop,
+};
+use crate::description::{DescribeMachine, DescribeTransitions, MachineDescription};
 use crate::guard::Guard;
 use crate::hmap::{AppendInner, HMap, HMapNil};
-use crate::process_result::{ProcessResult, ProcessResultInner, ProcessResultSubstate};
+use crate::process_event::{
+    ProcessEvent, ProcessEventDetailed, ProcessEventKind, ProcessOwnedEvent,
+};
+use crate::process_result::{
+    Explanation, GuardOutcome, ProcessDetails, ProcessError, ProcessResult, ProcessResultInner,
+    ProcessResultSubstate, TransitionInfo, TransitionKind, TransitionName,
+};
 use crate::transition::{
-    ForallTransition, ITransition, LoopTransition, ProcessByForallTransitions, Transition,
+    CatchAllTransition, Decision, DeferTransition, FireEvent, ForallInternalTransition,
+    ForallTransition, ITransition, IgnoreTransition, LoopTransition, OwnedLoopTransition,
+    OwnedTransition, Probe, ProcessByForallTransitions, Trace, Transition,
+};
+use crate::trigger::{
+    BorrowedEvent, EventKind, Family, Single, Static, Trigger, Unhandled, Wildcard,
+};
+use crate::utils::{
+    CoprodAsAny, CoprodAsAnyMut, CoprodWithRef, CoprodWithoutPhantomData, GetMutsFromCoprod,
+    GetRefsFromCoprod, InStatePath, IsTerminated, PairSelector, StatePath, TypeList, VertexName,
+    VertexSelector,
+};
+use crate::vert_handler::{ActiveConfiguration, EmptyVertexHandler, VertexHandler};
+use crate::vertex::{
+    FinalVertex, InitialPseudoState, OnEntry, Retain, Start, TerminationPseudoState, VertexSlot,
 };
-use crate::utils::{CoprodWithRef, CoprodWithoutPhantomData, GetRefsFromCoprod};
-use crate::vert_handler::{EmptyVertexHandler, VertexHandler};
-use crate::vertex::{InitialPseudoState, TerminationPseudoState};
-use crate::ProcessEvent;
-use frunk::coproduct::{CNil, CoproductEmbedder, CoproductSelector};
-use frunk::hlist::{h_cons, HList, Selector};
-use frunk::{Coproduct, HCons, HNil};
-use std::any::TypeId;
+use frunk::coproduct::{CNil, CoproductEmbedder, CoproductSelector, CoproductTaker};
+use frunk::hlist::{h_cons, HList};
+use frunk::{hlist, Coproduct, HCons, HNil, Hlist};
 use std::marker::PhantomData;
+use std::ops::Add;
 
 /// Main struct that encapsulates states and transitions.
 ///
@@ -42,13 +86,36 @@ pub struct StateMachine<
     Answer,
     GErr,
 > {
-    pub current: Current,
-    pub state: State,
-    pub vertexes: Vertexes,
-    pub vertices_handlers: VertHandlers,
-    pub transitions: Transitions,
-    pub forall_transitions: FAllTrans,
-    pub phantom: PhantomData<(Answer, GErr)>,
+    pub(crate) current: Current,
+    pub(crate) state: State,
+    pub(crate) vertexes: Vertexes,
+    pub(crate) vertices_handlers: VertHandlers,
+    pub(crate) transitions: Transitions,
+    pub(crate) forall_transitions: FAllTrans,
+    phantom: PhantomData<(Answer, GErr)>,
+}
+
+impl<Current, State, Vertexes, VertHandlers, Transitions, FAllTrans, Answer, GErr> Clone
+    for StateMachine<Current, State, Vertexes, VertHandlers, Transitions, FAllTrans, Answer, GErr>
+where
+    Current: Clone,
+    State: Clone,
+    Vertexes: Clone,
+    VertHandlers: Clone,
+    Transitions: Clone,
+    FAllTrans: Clone,
+{
+    fn clone(&self) -> Self {
+        StateMachine {
+            current: self.current.clone(),
+            state: self.state.clone(),
+            vertexes: self.vertexes.clone(),
+            vertices_handlers: self.vertices_handlers.clone(),
+            transitions: self.transitions.clone(),
+            forall_transitions: self.forall_transitions.clone(),
+            phantom: PhantomData,
+        }
+    }
 }
 
 impl<State, Answer, GErr>
@@ -58,7 +125,10 @@ impl<State, Answer, GErr>
             Coproduct<PhantomData<TerminationPseudoState>, CNil>,
         >,
         State,
-        HCons<InitialPseudoState, HCons<TerminationPseudoState, HNil>>,
+        HCons<
+            VertexSlot<InitialPseudoState, Retain>,
+            HCons<VertexSlot<TerminationPseudoState, Retain>, HNil>,
+        >,
         HCons<EmptyVertexHandler, HCons<EmptyVertexHandler, HNil>>,
         HMap<
             HCons<
@@ -76,7 +146,10 @@ impl<State, Answer, GErr>
         Self {
             current: Coproduct::inject(PhantomData::<InitialPseudoState>),
             state,
-            vertexes: h_cons(InitialPseudoState, h_cons(TerminationPseudoState, HNil)),
+            vertexes: h_cons(
+                VertexSlot::new(InitialPseudoState),
+                h_cons(VertexSlot::new(TerminationPseudoState), HNil),
+            ),
             vertices_handlers: h_cons(EmptyVertexHandler, h_cons(EmptyVertexHandler, HNil)),
             transitions: HMap::new().add(PhantomData, HNil).add(PhantomData, HNil),
             forall_transitions: HNil,
@@ -97,7 +170,8 @@ impl<
     >
     StateMachine<C, State, Vertexes, VertHandlers, HMap<Transitions>, FAllTransitions, Answer, GErr>
 {
-    /// Add an `Vertex` for state machine with specified handler.
+    /// Add an `Vertex` for state machine with specified handler. The vertex lives as long as the
+    /// state machine.
     ///
     /// More about vertices see in `umlsm::vertex` module.
     /// More about vertex handlers see in `umlsm::vert_handler` module.
@@ -108,7 +182,52 @@ impl<
     ) -> StateMachine<
         Coproduct<PhantomData<V>, C>,
         State,
-        HCons<V, Vertexes>,
+        HCons<VertexSlot<V, Retain>, Vertexes>,
+        HCons<VertHandler, VertHandlers>,
+        HMap<HCons<(PhantomData<V>, HNil), Transitions>>,
+        FAllTransitions,
+        Answer,
+        GErr,
+    >
+    where
+        C: CoproductEmbedder<Coproduct<PhantomData<V>, C>, Inds>,
+    {
+        self.add_vertex_slot(VertexSlot::new(vertex), vertex_handler)
+    }
+    /// Add an `Vertex` for state machine with specified handler. The vertex is constructed by
+    /// `factory` from the context and the event when it is entered, and dropped on exit.
+    ///
+    /// More about vertices see in `umlsm::vertex` module.
+    pub fn add_vertex_on_entry<V, F, VertHandler, Inds>(
+        self,
+        factory: F,
+        vertex_handler: VertHandler,
+    ) -> StateMachine<
+        Coproduct<PhantomData<V>, C>,
+        State,
+        HCons<VertexSlot<V, OnEntry<F>>, Vertexes>,
+        HCons<VertHandler, VertHandlers>,
+        HMap<HCons<(PhantomData<V>, HNil), Transitions>>,
+        FAllTransitions,
+        Answer,
+        GErr,
+    >
+    where
+        C: CoproductEmbedder<Coproduct<PhantomData<V>, C>, Inds>,
+    {
+        self.add_vertex_slot(VertexSlot::on_entry(factory), vertex_handler)
+    }
+    /// Add a `VertexSlot` with any policy for state machine with specified handler.
+    ///
+    /// More about vertices see in `umlsm::vertex` module.
+    pub fn add_vertex_slot<V, Policy, VertHandler, Inds>(
+        self,
+        vertex: VertexSlot<V, Policy>,
+        vertex_handler: VertHandler,
+    ) -> StateMachine<
+        Coproduct<PhantomData<V>, C>,
+        State,
+        HCons<VertexSlot<V, Policy>, Vertexes>,
         HCons<VertHandler, VertHandlers>,
         HMap<HCons<(PhantomData<V>, HNil), Transitions>>,
         FAllTransitions,
@@ -139,19 +258,22 @@ impl<
     }
     /// Add a transition between `Source` and `Target` vertex with specified `Action` and `Guard`.
     ///
+    /// Both vertices must be already added and must be different. A transition from a vertex to
+    /// itself does not compile, use `StateMachine::add_loop` instead.
+    ///
     /// More about actions see in `umlsm::action` module.
     /// More about guards see in `umlsm::guard` module.
     pub fn add_transition<AInput, A, G, S, E, Tar, AppendIdx, Idx, Out>(
         self,
         action: AInput,
         guard: G,
-        _target: PhantomData<Tar>,
+        target: PhantomData<Tar>,
     ) -> StateMachine<C, State, Vertexes, VertHandlers, HMap<Out>, FAllTransitions, Answer, GErr>
     where
-        Vertexes: Selector<S, Idx>,
+        Vertexes: PairSelector<S, Tar, Idx>,
         Transitions: AppendInner<
             PhantomData<S>,
-            Transition<S, State, E, A, G, Tar, Answer, GErr>,
+            Transition<S, State, Single<E>, A, G, Tar, Answer, GErr>,
             AppendIdx,
             Out,
         >,
@@ -159,11 +281,38 @@ impl<
         A: Action<S, State, E, Tar, Answer>,
         G: Guard<E, GErr>,
         S: 'static,
+        E: 'static,
+        Tar: 'static,
+    {
+        self.add_transition_with_trigger::<A, G, S, Single<E>, Tar, AppendIdx, Idx, Out>(
+            action.into(),
+            guard,
+            target,
+        )
+    }
+    /// Add a transition between `Source` and `Target` vertex that started by `Trigger`.
+    ///
+    /// More about triggers see in `umlsm::trigger` module.
+    pub fn add_transition_with_trigger<A, G, S, Trig, Tar, AppendIdx, Idx, Out>(
+        self,
+        action: A,
+        guard: G,
+        _target: PhantomData<Tar>,
+    ) -> StateMachine<C, State, Vertexes, VertHandlers, HMap<Out>, FAllTransitions, Answer, GErr>
+    where
+        Vertexes: PairSelector<S, Tar, Idx>,
+        Transitions: AppendInner<
+            PhantomData<S>,
+            Transition<S, State, Trig, A, G, Tar, Answer, GErr>,
+            AppendIdx,
+            Out,
+        >,
+        Trig: Trigger,
+        A: for<'a> Action<S, State, Trig::Event<'a>, Tar, Answer>,
+        G: for<'a> Guard<Trig::Event<'a>, GErr>,
+        S: 'static,
         Tar: 'static,
     {
-        if TypeId::of::<S>() == TypeId::of::<Tar>() {
-            panic!("If you want to add loop transition, use StateMachine::add_loop instead.")
-        }
         let StateMachine {
             current,
             state,
@@ -178,7 +327,7 @@ impl<
             state,
             vertexes,
             vertices_handlers,
-            transitions: transitions.append_inner(Transition::new(action.into(), guard)),
+            transitions: transitions.append_inner(Transition::new(action, guard)),
             forall_transitions,
             phantom,
         }
@@ -189,23 +338,82 @@ impl<
     /// More about actions see in `umlsm::action` module.
     /// More about guards see in `umlsm::guard` module.
     pub fn add_transition_forall<A, G, E, Tar>(
+        self,
+        action: A,
+        guard: G,
+        target: PhantomData<Tar>,
+    ) -> StateMachine<
+        C,
+        State,
+        Vertexes,
+        VertHandlers,
+        HMap<Transitions>,
+        HCons<ForallTransition<State, Single<E>, A, G, Tar, Answer, GErr, HNil>, FAllTransitions>,
+        Answer,
+        GErr,
+    >
+    where
+        A: ForallAction<State, E, Tar, Answer>,
+        G: Guard<E, GErr>,
+        E: 'static,
+    {
+        self.add_transition_forall_except(action, guard, target, PhantomData::<HNil>)
+    }
+    /// Add a transition between all vertices in state machine (except `Target` vertex and vertices
+    /// from `Except` list) and `Target` vertex with specified `Action` and `Guard`.
+    ///
+    /// `Except` is a type-level list of vertices, for example `frunk::Hlist![A, B]`.
+    pub fn add_transition_forall_except<A, G, E, Tar, Except>(
+        self,
+        action: A,
+        guard: G,
+        target: PhantomData<Tar>,
+        except: PhantomData<Except>,
+    ) -> StateMachine<
+        C,
+        State,
+        Vertexes,
+        VertHandlers,
+        HMap<Transitions>,
+        HCons<ForallTransition<State, Single<E>, A, G, Tar, Answer, GErr, Except>, FAllTransitions>,
+        Answer,
+        GErr,
+    >
+    where
+        A: ForallAction<State, E, Tar, Answer>,
+        G: Guard<E, GErr>,
+        E: 'static,
+        Except: TypeList,
+    {
+        self.add_transition_forall_with_trigger::<A, G, Single<E>, Tar, Except>(
+            action, guard, target, except,
+        )
+    }
+    /// Add a transition between all vertices in state machine (except `Target` vertex and vertices
+    /// from `Except` list) and `Target` vertex that started by `Trigger`.
+    ///
+    /// More about triggers see in `umlsm::trigger` module.
+    pub fn add_transition_forall_with_trigger<A, G, Trig, Tar, Except>(
         self,
         action: A,
         guard: G,
         _target: PhantomData<Tar>,
+        _except: PhantomData<Except>,
     ) -> StateMachine<
         C,
         State,
         Vertexes,
         VertHandlers,
         HMap<Transitions>,
-        HCons<ForallTransition<A, G>, FAllTransitions>,
+        HCons<ForallTransition<State, Trig, A, G, Tar, Answer, GErr, Except>, FAllTransitions>,
         Answer,
         GErr,
     >
     where
-        A: Clone,
-        G: Guard<E, GErr> + Clone,
+        Trig: Trigger,
+        A: for<'a> ForallAction<State, Trig::Event<'a>, Tar, Answer>,
+        G: for<'a> Guard<Trig::Event<'a>, GErr>,
+        Except: TypeList,
     {
         let StateMachine {
             current,
@@ -226,21 +434,61 @@ impl<
             phantom,
         }
     }
-    /// Add an loop for specified `Vertex` with `Action` and `Guard`.
-    pub fn add_loop<A, G, Vertex, E, AppendIdx, Out>(
+    /// Add an internal transition for all vertices in state machine (except vertices from `Except`
+    /// list) with specified `Action` and `Guard`.
+    ///
+    /// Internal transition does not leave the current vertex, so neither `ExitVertex` nor
+    /// `EntryVertex` are called.
+    pub fn add_internal_forall<A, G, E, Except>(
         self,
         action: A,
         guard: G,
-    ) -> StateMachine<C, State, Vertexes, VertHandlers, HMap<Out>, FAllTransitions, Answer, GErr>
-    where
-        Transitions: AppendInner<
-            PhantomData<Vertex>,
-            LoopTransition<Vertex, State, E, A, G, Answer, GErr>,
-            AppendIdx,
-            Out,
+        except: PhantomData<Except>,
+    ) -> StateMachine<
+        C,
+        State,
+        Vertexes,
+        VertHandlers,
+        HMap<Transitions>,
+        HCons<
+            ForallInternalTransition<State, Single<E>, A, G, Answer, GErr, Except>,
+            FAllTransitions,
         >,
-        A: ActionLoop<Vertex, State, E, Answer>,
+        Answer,
+        GErr,
+    >
+    where
+        A: InternalAction<State, E, Answer>,
         G: Guard<E, GErr>,
+        E: 'static,
+        Except: TypeList,
+    {
+        self.add_internal_forall_with_trigger::<A, G, Single<E>, Except>(action, guard, except)
+    }
+    /// Add an internal transition for all vertices in state machine (except vertices from `Except`
+    /// list) that started by `Trigger`.
+    ///
+    /// More about triggers see in `umlsm::trigger` module.
+    pub fn add_internal_forall_with_trigger<A, G, Trig, Except>(
+        self,
+        action: A,
+        guard: G,
+        _except: PhantomData<Except>,
+    ) -> StateMachine<
+        C,
+        State,
+        Vertexes,
+        VertHandlers,
+        HMap<Transitions>,
+        HCons<ForallInternalTransition<State, Trig, A, G, Answer, GErr, Except>, FAllTransitions>,
+        Answer,
+        GErr,
+    >
+    where
+        Trig: Trigger,
+        A: for<'a> InternalAction<State, Trig::Event<'a>, Answer>,
+        G: for<'a> Guard<Trig::Event<'a>, GErr>,
+        Except: TypeList,
     {
         let StateMachine {
             current,
@@ -256,145 +504,1056 @@ impl<
             state,
             vertexes,
             vertices_handlers,
-            transitions: transitions.append_inner(LoopTransition::new(action, guard)),
-            forall_transitions,
+            transitions,
+            forall_transitions: forall_transitions
+                .prepend(ForallInternalTransition::new(action, guard)),
             phantom,
         }
     }
-}
-
-/// An interface for checking current vertex of machine.
-pub trait CurrentStateIs<Idx, Inner> {
-    /// Check that current state of state machine is `T`
-    fn is<T>(&self) -> bool
-    where
-        Inner: CoproductSelector<PhantomData<T>, Idx>;
-}
-
-impl<C, State, Vertexes, VertHandlers, Transitions, FAllTransitions, Answer, Idx, GErr>
-    CurrentStateIs<Idx, C>
-    for StateMachine<C, State, Vertexes, VertHandlers, Transitions, FAllTransitions, Answer, GErr>
-{
-    fn is<T>(&self) -> bool
-    where
-        C: CoproductSelector<PhantomData<T>, Idx>,
-    {
-        self.current.get().is_some()
-    }
-}
-
-impl<C, State, Vertexes, VertHandlers, Transitions, FAllTransitions, Answer, GErr>
-    StateMachine<C, State, Vertexes, VertHandlers, Transitions, FAllTransitions, Answer, GErr>
-{
-    pub fn get_current<'a>(
-        &'a self,
-    ) -> <<C as CoprodWithoutPhantomData>::WithoutPD as CoprodWithRef<'a>>::CoprodWithRef
-    where
-        Vertexes: GetRefsFromCoprod<
-            'a,
-            C,
-            Out = <<C as CoprodWithoutPhantomData>::WithoutPD as CoprodWithRef<'a>>::CoprodWithRef,
-        >,
-        C: CoprodWithoutPhantomData,
-        C::WithoutPD: CoprodWithRef<'a>,
-    {
-        self.vertexes.get_refs(&self.current)
-    }
-
-    pub fn get_current_as<'a, T, Idx>(&'a self) -> Option<&'a T>
-    where
-        C: CoprodWithoutPhantomData,
-        C::WithoutPD: CoprodWithRef<'a>,
-        Vertexes: GetRefsFromCoprod<
-            'a,
-            C,
-            Out = <<C as CoprodWithoutPhantomData>::WithoutPD as CoprodWithRef<'a>>::CoprodWithRef,
-        >,
-        <<C as CoprodWithoutPhantomData>::WithoutPD as CoprodWithRef<'a>>::CoprodWithRef:
-            CoproductSelector<&'a T, Idx>,
-    {
-        self.get_current().get().map(|&u| u)
-    }
-
-    /// Get specified vertex.
-    pub fn get_vertex<'a, T, Idx>(&'a self) -> &'a T
-    where
-        Vertexes: Selector<T, Idx>,
-    {
-        self.vertexes.get()
-    }
-}
-
-impl<
+    /// Add a catch-all transition between `Source` and `Target` vertex. It is started by an event
+    /// of any type, when neither typed nor forall transitions from `Source` accept it.
+    ///
+    /// `Action` and `Guard` receive the event as `umlsm::trigger::Unhandled`.
+    pub fn add_catch_all<A, G, S, Tar, Idx>(
+        self,
+        action: A,
+        guard: G,
+        _target: PhantomData<Tar>,
+    ) -> StateMachine<
         C,
         State,
         Vertexes,
         VertHandlers,
-        Transitions,
-        FAllTransitions,
-        E,
-        OtherTR,
+        HMap<Transitions>,
+        <FAllTransitions as Add<
+            Hlist![CatchAllTransition<S, Transition<S, State, Wildcard, A, G, Tar, Answer, GErr>>],
+        >>::Output,
         Answer,
         GErr,
-        OtherVH,
-        OtherC,
-    > ProcessEvent<E, Answer, GErr, (OtherTR, OtherVH, OtherC)>
-    for StateMachine<
+    >
+    where
+        Vertexes: PairSelector<S, Tar, Idx>,
+        FAllTransitions: Add<
+            Hlist![CatchAllTransition<S, Transition<S, State, Wildcard, A, G, Tar, Answer, GErr>>],
+        >,
+        A: for<'a> Action<S, State, Unhandled<'a>, Tar, Answer>,
+        G: for<'a> Guard<Unhandled<'a>, GErr>,
+        S: 'static,
+        Tar: 'static,
+    {
+        let StateMachine {
+            current,
+            state,
+            vertexes,
+            vertices_handlers,
+            transitions,
+            forall_transitions,
+            phantom,
+        } = self;
+        StateMachine {
+            current,
+            state,
+            vertexes,
+            vertices_handlers,
+            transitions,
+            forall_transitions: forall_transitions
+                + hlist![CatchAllTransition::new(Transition::<
+                    S,
+                    State,
+                    Wildcard,
+                    A,
+                    G,
+                    Tar,
+                    Answer,
+                    GErr,
+                >::new(
+                    action, guard
+                ))],
+            phantom,
+        }
+    }
+    /// Add a catch-all internal transition for `Source` vertex. It is started by an event of any
+    /// type, when neither typed nor forall transitions from `Source` accept it.
+    ///
+    /// `Action` and `Guard` receive the event as `umlsm::trigger::Unhandled`.
+    pub fn add_internal_catch_all<A, G, S>(
+        self,
+        action: A,
+        guard: G,
+    ) -> StateMachine<
         C,
         State,
         Vertexes,
         VertHandlers,
         HMap<Transitions>,
-        FAllTransitions,
+        <FAllTransitions as Add<
+            Hlist![CatchAllTransition<
+                S,
+                ForallInternalTransition<State, Wildcard, A, G, Answer, GErr, HNil>,
+            >],
+        >>::Output,
         Answer,
         GErr,
     >
-where
-    Transitions: ITransition<C, State, E, C, Vertexes, Answer, GErr, OtherTR>,
-    VertHandlers: VertexHandler<Vertexes, C, E, Answer, GErr, OtherVH>,
-    C: ProcessByForallTransitions<FAllTransitions, State, E, Vertexes, Answer, C, GErr, OtherC>,
-{
-    fn process(&mut self, event: &E) -> ProcessResult<Answer, GErr> {
-        use ProcessResultInner::*;
-
-        match self
-            .vertices_handlers
-            .process(&mut self.vertexes, &self.current, event)
+    where
+        FAllTransitions: Add<
+            Hlist![CatchAllTransition<
+                S,
+                ForallInternalTransition<State, Wildcard, A, G, Answer, GErr, HNil>,
+            >],
+        >,
+        A: for<'a> InternalAction<State, Unhandled<'a>, Answer>,
+        G: for<'a> Guard<Unhandled<'a>, GErr>,
+        S: 'static,
+    {
+        let StateMachine {
+            current,
+            state,
+            vertexes,
+            vertices_handlers,
+            transitions,
+            forall_transitions,
+            phantom,
+        } = self;
+        StateMachine {
+            current,
+            state,
+            vertexes,
+            vertices_handlers,
+            transitions,
+            forall_transitions: forall_transitions
+                + hlist![CatchAllTransition::new(ForallInternalTransition::<
+                    State,
+                    Wildcard,
+                    A,
+                    G,
+                    Answer,
+                    GErr,
+                    HNil,
+                >::new(
+                    action, guard
+                ))],
+            phantom,
+        }
+    }
+    /// Add an loop for specified `Vertex` with `Action` and `Guard`.
+    pub fn add_loop<A, G, Vertex, E, AppendIdx, Out>(
+        self,
+        action: A,
+        guard: G,
+    ) -> StateMachine<C, State, Vertexes, VertHandlers, HMap<Out>, FAllTransitions, Answer, GErr>
+    where
+        Transitions: AppendInner<
+            PhantomData<Vertex>,
+            LoopTransition<Vertex, State, Single<E>, A, G, Answer, GErr>,
+            AppendIdx,
+            Out,
+        >,
+        A: ActionLoop<Vertex, State, E, Answer>,
+        G: Guard<E, GErr>,
+        E: 'static,
+    {
+        self.add_loop_with_trigger::<A, G, Vertex, Single<E>, AppendIdx, Out>(action, guard)
+    }
+    /// Add an loop for specified `Vertex` that started by `Trigger`.
+    ///
+    /// More about triggers see in `umlsm::trigger` module.
+    pub fn add_loop_with_trigger<A, G, Vertex, Trig, AppendIdx, Out>(
+        self,
+        action: A,
+        guard: G,
+    ) -> StateMachine<C, State, Vertexes, VertHandlers, HMap<Out>, FAllTransitions, Answer, GErr>
+    where
+        Transitions: AppendInner<
+            PhantomData<Vertex>,
+            LoopTransition<Vertex, State, Trig, A, G, Answer, GErr>,
+            AppendIdx,
+            Out,
+        >,
+        Trig: Trigger,
+        A: for<'a> ActionLoop<Vertex, State, Trig::Event<'a>, Answer>,
+        G: for<'a> Guard<Trig::Event<'a>, GErr>,
+    {
+        let StateMachine {
+            current,
+            state,
+            vertexes,
+            vertices_handlers,
+            transitions,
+            forall_transitions,
+            phantom,
+        } = self;
+        StateMachine {
+            current,
+            state,
+            vertexes,
+            vertices_handlers,
+            transitions: transitions.append_inner(LoopTransition::new(action, guard)),
+            forall_transitions,
+            phantom,
+        }
+    }
+    /// Ignore the event `E` in the specified `Vertex` explicitly. Processing of it returns
+    /// `ProcessResult::Ignored` instead of `NoTransitions`.
+    pub fn add_ignore<Vertex, E, AppendIdx, Out>(
+        self,
+    ) -> StateMachine<C, State, Vertexes, VertHandlers, HMap<Out>, FAllTransitions, Answer, GErr>
+    where
+        Transitions:
+            AppendInner<PhantomData<Vertex>, IgnoreTransition<Vertex, Single<E>>, AppendIdx, Out>,
+        E: 'static,
+    {
+        self.add_ignore_with_trigger::<Vertex, Single<E>, AppendIdx, Out>()
+    }
+    /// Ignore events that are accepted by `Trigger` in the specified `Vertex` explicitly.
+    ///
+    /// More about triggers see in `umlsm::trigger` module.
+    pub fn add_ignore_with_trigger<Vertex, Trig, AppendIdx, Out>(
+        self,
+    ) -> StateMachine<C, State, Vertexes, VertHandlers, HMap<Out>, FAllTransitions, Answer, GErr>
+    where
+        Transitions:
+            AppendInner<PhantomData<Vertex>, IgnoreTransition<Vertex, Trig>, AppendIdx, Out>,
+        Trig: Trigger,
+    {
+        let StateMachine {
+            current,
+            state,
+            vertexes,
+            vertices_handlers,
+            transitions,
+            forall_transitions,
+            phantom,
+            ..
+        } = self;
+        StateMachine {
+            current,
+            state,
+            vertexes,
+            vertices_handlers,
+            transitions: transitions.append_inner(IgnoreTransition::new()),
+            forall_transitions,
+            phantom,
+        }
+    }
+    /// Defer the event `E` in the specified `Vertex` explicitly. Processing of it returns
+    /// `ProcessResult::Deferred` instead of `NoTransitions`, so the caller can keep the event and
+    /// process it again after the current vertex is changed.
+    pub fn add_defer<Vertex, E, AppendIdx, Out>(
+        self,
+    ) -> StateMachine<C, State, Vertexes, VertHandlers, HMap<Out>, FAllTransitions, Answer, GErr>
+    where
+        Transitions:
+            AppendInner<PhantomData<Vertex>, DeferTransition<Vertex, Single<E>>, AppendIdx, Out>,
+        E: 'static,
+    {
+        self.add_defer_with_trigger::<Vertex, Single<E>, AppendIdx, Out>()
+    }
+    /// Defer events that are accepted by `Trigger` in the specified `Vertex` explicitly.
+    ///
+    /// More about triggers see in `umlsm::trigger` module.
+    pub fn add_defer_with_trigger<Vertex, Trig, AppendIdx, Out>(
+        self,
+    ) -> StateMachine<C, State, Vertexes, VertHandlers, HMap<Out>, FAllTransitions, Answer, GErr>
+    where
+        Transitions:
+            AppendInner<PhantomData<Vertex>, DeferTransition<Vertex, Trig>, AppendIdx, Out>,
+        Trig: Trigger,
+    {
+        let StateMachine {
+            current,
+            state,
+            vertexes,
+            vertices_handlers,
+            transitions,
+            forall_transitions,
+            phantom,
+            ..
+        } = self;
+        StateMachine {
+            current,
+            state,
+            vertexes,
+            vertices_handlers,
+            transitions: transitions.append_inner(DeferTransition::new()),
+            forall_transitions,
+            phantom,
+        }
+    }
+    /// Add a transition between `Source` and `Target` vertex with an `OwnedAction`, which takes the
+    /// event by value. It is started only by `StateMachine::process_owned`.
+    ///
+    /// More about actions see in `umlsm::action` module.
+    pub fn add_owned_transition<A, G, S, E, Tar, AppendIdx, Idx, Out>(
+        self,
+        action: A,
+        guard: G,
+        _target: PhantomData<Tar>,
+    ) -> StateMachine<C, State, Vertexes, VertHandlers, HMap<Out>, FAllTransitions, Answer, GErr>
+    where
+        Vertexes: PairSelector<S, Tar, Idx>,
+        Transitions: AppendInner<
+            PhantomData<S>,
+            OwnedTransition<S, State, E, A, G, Tar, Answer, GErr>,
+            AppendIdx,
+            Out,
+        >,
+        A: OwnedAction<S, State, E, Tar, Answer>,
+        G: Guard<E, GErr>,
+    {
+        let StateMachine {
+            current,
+            state,
+            vertexes,
+            vertices_handlers,
+            transitions,
+            forall_transitions,
+            phantom,
+        } = self;
+        StateMachine {
+            current,
+            state,
+            vertexes,
+            vertices_handlers,
+            transitions: transitions.append_inner(OwnedTransition::new(action, guard)),
+            forall_transitions,
+            phantom,
+        }
+    }
+    /// Add an loop for specified `Vertex` with an `OwnedActionLoop`, which takes the event by value.
+    /// It is started only by `StateMachine::process_owned`.
+    pub fn add_owned_loop<A, G, Vertex, E, AppendIdx, Out>(
+        self,
+        action: A,
+        guard: G,
+    ) -> StateMachine<C, State, Vertexes, VertHandlers, HMap<Out>, FAllTransitions, Answer, GErr>
+    where
+        Transitions: AppendInner<
+            PhantomData<Vertex>,
+            OwnedLoopTransition<Vertex, State, E, A, G, Answer, GErr>,
+            AppendIdx,
+            Out,
+        >,
+        A: OwnedActionLoop<Vertex, State, E, Answer>,
+        G: Guard<E, GErr>,
+    {
+        let StateMachine {
+            current,
+            state,
+            vertexes,
+            vertices_handlers,
+            transitions,
+            forall_transitions,
+            phantom,
+        } = self;
+        StateMachine {
+            current,
+            state,
+            vertexes,
+            vertices_handlers,
+            transitions: transitions.append_inner(OwnedLoopTransition::new(action, guard)),
+            forall_transitions,
+            phantom,
+        }
+    }
+}
+
+/// An interface for checking current vertex of machine.
+pub trait CurrentStateIs<Idx, Inner> {
+    /// Check that current state of state machine is `T`
+    fn is<T>(&self) -> bool
+    where
+        Inner: CoproductSelector<PhantomData<T>, Idx>;
+}
+
+impl<C, State, Vertexes, VertHandlers, Transitions, FAllTransitions, Answer, Idx, GErr>
+    CurrentStateIs<Idx, C>
+    for StateMachine<C, State, Vertexes, VertHandlers, Transitions, FAllTransitions, Answer, GErr>
+{
+    fn is<T>(&self) -> bool
+    where
+        C: CoproductSelector<PhantomData<T>, Idx>,
+    {
+        self.current.get().is_some()
+    }
+}
+
+impl<C, State, Vertexes, VertHandlers, Transitions, FAllTransitions, Answer, GErr> IsTerminated
+    for StateMachine<C, State, Vertexes, VertHandlers, Transitions, FAllTransitions, Answer, GErr>
+where
+    C: IsTerminated,
+{
+    /// Check that the state machine is in a final vertex.
+    fn is_terminated(&self) -> bool {
+        self.current.is_terminated()
+    }
+}
+
+impl<C, State, Vertexes, VertHandlers, Transitions, FAllTransitions, Answer, GErr>
+    StateMachine<C, State, Vertexes, VertHandlers, Transitions, FAllTransitions, Answer, GErr>
+{
+    /// Describe vertices and transitions of the state machine, including sub state machines.
+    ///
+    /// More about descriptions see in `umlsm::description` module.
+    pub fn describe(&self) -> MachineDescription
+    where
+        Self: DescribeMachine,
+    {
+        DescribeMachine::describe(self)
+    }
+
+    /// The name of the current vertex. A `StateMachineVertex` is named by the type name of its
+    /// `IDX`, other vertices by their type names.
+    pub fn current_name(&self) -> &'static str
+    where
+        VertHandlers: ActiveConfiguration<Vertexes, C>,
+    {
+        self.vertices_handlers
+            .vertex_name(&self.vertexes, &self.current)
+    }
+
+    /// Names of the current vertex and of the current vertices of active sub state machines, from
+    /// the outermost to the innermost one, like `["Checkout", "Payment", "AwaitingCard"]`.
+    pub fn active_configuration(&self) -> Vec<&'static str>
+    where
+        VertHandlers: ActiveConfiguration<Vertexes, C>,
+    {
+        let mut configuration = Vec::new();
+        self.vertices_handlers.active_configuration(
+            &self.vertexes,
+            &self.current,
+            &mut configuration,
+        );
+        configuration
+    }
+
+    /// Check that the state machine is in the vertices of `P` at all levels of sub state machines,
+    /// starting from this one. Sub state machine vertices are written as `IDX`:
+    /// `sm.is_in::<(Inner, WaitForCode), _>()`. The innermost vertex of the path may be a sub
+    /// state machine in any state.
+    ///
+    /// Each vertex of the path is resolved at compile time, so a path through a vertex that is not
+    /// a sub state machine, or to a vertex that its state machine does not have, does not compile.
+    pub fn is_in<P: StatePath, Idx>(&self) -> bool
+    where
+        Self: InStatePath<P::Path, Idx>,
+    {
+        self.is_in_path()
+    }
+
+    /// Type names of the events that have transitions from the current vertex, including forall
+    /// and catch-all (`_`) ones. Guards are not called, use `can_process` to check them.
+    ///
+    /// Events of the sub state machine of the current vertex are not listed.
+    pub fn enabled_event_types(&self) -> Vec<&'static str>
+    where
+        C: VertexName + IsTerminated,
+        Transitions: DescribeTransitions,
+        FAllTransitions: DescribeTransitions,
+    {
+        if self.current.is_terminated() {
+            return Vec::new();
+        }
+        let current = self.current.vertex_name();
+        let mut transitions = Vec::new();
+        self.transitions.describe(&mut transitions);
+        self.forall_transitions.describe(&mut transitions);
+
+        let mut events = Vec::new();
+        for transition in transitions {
+            if transition.starts_from(current) {
+                for event in transition.events {
+                    if !events.contains(&event) {
+                        events.push(event);
+                    }
+                }
+            }
+        }
+        events
+    }
+
+    /// Get the current vertex as a `Coproduct` of references. Returns `None` if the current vertex
+    /// does not exist, e.g. it is constructed on entry and has not been entered.
+    pub fn get_current<'a>(
+        &'a self,
+    ) -> Option<<<C as CoprodWithoutPhantomData>::WithoutPD as CoprodWithRef<'a>>::CoprodWithRef>
+    where
+        Vertexes: GetRefsFromCoprod<
+            'a,
+            C,
+            Out = <<C as CoprodWithoutPhantomData>::WithoutPD as CoprodWithRef<'a>>::CoprodWithRef,
+        >,
+        C: CoprodWithoutPhantomData,
+        C::WithoutPD: CoprodWithRef<'a>,
+    {
+        self.vertexes.get_refs(&self.current)
+    }
+
+    pub fn get_current_as<'a, T, Idx>(&'a self) -> Option<&'a T>
+    where
+        C: CoprodWithoutPhantomData,
+        C::WithoutPD: CoprodWithRef<'a>,
+        Vertexes: GetRefsFromCoprod<
+            'a,
+            C,
+            Out = <<C as CoprodWithoutPhantomData>::WithoutPD as CoprodWithRef<'a>>::CoprodWithRef,
+        >,
+        <<C as CoprodWithoutPhantomData>::WithoutPD as CoprodWithRef<'a>>::CoprodWithRef:
+            CoproductSelector<&'a T, Idx>,
+    {
+        self.get_current()?.get().copied()
+    }
+
+    /// Get the current vertex mutably as a `Coproduct` of mutable references. The current vertex
+    /// itself is changed only by processing events. Returns `None` as `get_current`.
+    pub fn get_current_mut<'a>(&'a mut self) -> Option<<Vertexes as GetMutsFromCoprod<'a, C>>::Out>
+    where
+        Vertexes: GetMutsFromCoprod<'a, C>,
+    {
+        self.vertexes.get_muts(&self.current)
+    }
+
+    /// Get the current vertex mutably, if it is `T`.
+    pub fn get_current_as_mut<'a, T, Idx>(&'a mut self) -> Option<&'a mut T>
+    where
+        Vertexes: GetMutsFromCoprod<'a, C>,
+        Vertexes::Out: CoproductTaker<&'a mut T, Idx>,
+    {
+        self.get_current_mut()?.take()
+    }
+
+    /// Call `f` with the current vertex as `&dyn Trait`, where `dyn Trait` is a `Family` of
+    /// vertex types declared by `umlsm::family!`. Returns `None` if the current vertex is not a
+    /// member of the family, such as `InitialPseudoState`.
+    pub fn with_current<'a, T, R>(
+        &'a self,
+        f: impl for<'v> FnOnce(&'v T::Object<'v>) -> R,
+    ) -> Option<R>
+    where
+        T: Family + ?Sized,
+        Vertexes: GetRefsFromCoprod<'a, C>,
+        Vertexes::Out: CoprodAsAny<'a>,
+    {
+        let vertex = self.vertexes.get_refs(&self.current)?.into_any();
+        T::cast(vertex).map(|vertex| f(T::shorten(vertex)))
+    }
+
+    /// Same as `with_current`, but `f` may change the current vertex.
+    pub fn with_current_mut<'a, T, R>(
+        &'a mut self,
+        f: impl for<'v> FnOnce(&'v mut T::Object<'v>) -> R,
+    ) -> Option<R>
+    where
+        T: Family + ?Sized,
+        Vertexes: GetMutsFromCoprod<'a, C>,
+        Vertexes::Out: CoprodAsAnyMut<'a>,
+    {
+        let vertex = self.vertexes.get_muts(&self.current)?.into_any_mut();
+        T::cast_mut(vertex).map(|vertex| f(T::shorten_mut(vertex)))
+    }
+
+    /// Get specified vertex. Returns `None` if the vertex is constructed on entry and the state
+    /// machine is not in it.
+    pub fn get_vertex<T, Idx>(&self) -> Option<&T>
+    where
+        Vertexes: VertexSelector<T, Idx>,
+    {
+        self.vertexes.vertex()
+    }
+
+    /// Get specified vertex mutably. Returns `None` if the vertex is constructed on entry and the
+    /// state machine is not in it.
+    pub fn get_vertex_mut<T, Idx>(&mut self) -> Option<&mut T>
+    where
+        Vertexes: VertexSelector<T, Idx>,
+    {
+        self.vertexes.vertex_mut()
+    }
+
+    /// The context of the state machine, which is passed to guards and actions.
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// The context of the state machine, which can be changed between events.
+    pub fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    /// Take the state machine apart into the context and the list of vertex slots, if it is in a
+    /// final vertex. Otherwise returns the state machine back.
+    pub fn into_parts(self) -> Result<(State, Vertexes), Self>
+    where
+        C: IsTerminated,
+    {
+        if self.current.is_terminated() {
+            Ok((self.state, self.vertexes))
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Take the state machine apart, if it has reached the final vertex `T`, such as
+    /// `Final<Outcome>` or `TerminationPseudoState`. Returns the context and the final vertex,
+    /// otherwise returns the state machine back.
+    pub fn finish<T, Idx, VIdx>(mut self) -> Result<(State, T), Self>
+    where
+        T: FinalVertex,
+        C: CoproductSelector<PhantomData<T>, Idx>,
+        Vertexes: VertexSelector<T, VIdx>,
+    {
+        if self.current.get().is_none() {
+            return Err(self);
+        }
+        match VertexSelector::<T, VIdx>::slot_mut(&mut self.vertexes).take() {
+            Some(vertex) => Ok((self.state, vertex)),
+            None => Err(self),
+        }
+    }
+
+    /// Process an event like `ProcessEvent::process`, and describe the fired transition: the source
+    /// and target vertices, the event, the kind of the transition and the outcome of guards.
+    pub fn process_detailed<E, Other>(&mut self, event: &E) -> ProcessDetails<Answer, GErr>
+    where
+        E: 'static,
+        Self: ProcessEventDetailed<Static<E>, Answer, GErr, Other>,
+    {
+        self.process_kind_detailed(event)
+    }
+
+    /// Check whether the event would be handled, without calling actions or changing the state.
+    /// Guards are called as by `ProcessEvent::process`. Returns the transition that would be
+    /// fired or the reason why the event would not be handled.
+    ///
+    /// Only transitions that take the event by reference are checked, as by `process`.
+    pub fn can_process<E, Other>(&self, event: &E) -> Result<TransitionInfo, ProcessError<GErr>>
+    where
+        E: 'static,
+        Self: ProcessEventKind<Static<E>, Answer, GErr, Other>,
+    {
+        self.can_process_kind(event)
+    }
+
+    /// Explain how a transition would be chosen for the event, e.g. why it is not handled. Walks
+    /// the same path as `ProcessEvent::process` and collects every checked transition, without
+    /// calling actions or changing the state.
+    pub fn explain<E, Other>(&self, event: &E) -> Explanation<GErr>
+    where
+        E: 'static,
+        GErr: Clone,
+        Self: ProcessEventKind<Static<E>, Answer, GErr, Other>,
+    {
+        self.explain_kind(event)
+    }
+
+    /// Run the initial transition, which is written as `initial => Target | action` in
+    /// `state_machine!`, and return its answer.
+    pub fn start<Other>(&mut self) -> ProcessResult<Answer, GErr>
+    where
+        Self: ProcessEvent<Start, Answer, GErr, Other>,
+    {
+        self.process(&Start)
+    }
+
+    /// Process an event that borrows data, such as `NewMessage<'a>`.
+    ///
+    /// More about borrowed events see in `umlsm::trigger` module.
+    pub fn process_borrowed<'a, E, Other>(&mut self, event: &'a E) -> ProcessResult<Answer, GErr>
+    where
+        E: BorrowedEvent<'a>,
+        Self: ProcessEventKind<E::Kind, Answer, GErr, Other>,
+    {
+        self.process_kind(event)
+    }
+}
+
+/// The transition that is chosen by probing.
+enum Chosen {
+    Transitions(usize),
+    Forall(usize),
+    CatchAll(usize),
+}
+
+impl Chosen {
+    fn kind(&self) -> TransitionKind {
+        match self {
+            Chosen::Transitions(_) => TransitionKind::Normal,
+            Chosen::Forall(_) => TransitionKind::Forall,
+            Chosen::CatchAll(_) => TransitionKind::CatchAll,
+        }
+    }
+
+    fn info(&self, transition: TransitionName) -> TransitionInfo {
+        let kind = match self.kind() {
+            TransitionKind::Normal if transition.source == transition.target => {
+                TransitionKind::Loop
+            }
+            kind => kind,
+        };
+        TransitionInfo { transition, kind }
+    }
+}
+
+impl<C, State, Vertexes, VertHandlers, Transitions, FAllTransitions, Answer, GErr>
+    StateMachine<C, State, Vertexes, VertHandlers, HMap<Transitions>, FAllTransitions, Answer, GErr>
+{
+    /// Pass the event to the current sub state machine and probe transitions from the current
+    /// vertex. Returns the chosen transition or the result if there is nothing to fire.
+    fn choose<'e, E, OtherTR, OtherVH, OtherC>(
+        &mut self,
+        event: &'e E::Of<'e>,
+        owned_event: bool,
+    ) -> Result<Chosen, ProcessResult<Answer, GErr>>
+    where
+        E: EventKind,
+        Transitions: ITransition<C, State, E, C, Vertexes, Answer, GErr, OtherTR>,
+        VertHandlers: VertexHandler<Vertexes, C, E, Answer, GErr, OtherVH>,
+        C: ProcessByForallTransitions<FAllTransitions, State, E, Vertexes, Answer, C, GErr, OtherC>
+            + IsTerminated,
+    {
+        if self.current.is_terminated() {
+            return Err(ProcessResult::Terminated);
+        }
+        let decided = match self
+            .vertices_handlers
+            .process(&mut self.vertexes, &self.current, event)
         {
-            ProcessResultSubstate::Handled(answer) => return ProcessResult::Handled(answer),
-            ProcessResultSubstate::NoTransitions => return ProcessResult::NoTransitions,
-            ProcessResultSubstate::GuardErr(ge) => return ProcessResult::GuardErr(ge),
-            ProcessResultSubstate::MustLeaveState => {}
+            ProcessResultSubstate::Handled(answer) => return Err(ProcessResult::Handled(answer)),
+            ProcessResultSubstate::Ignored => return Err(ProcessResult::Ignored),
+            ProcessResultSubstate::Deferred => return Err(ProcessResult::Deferred),
+            ProcessResultSubstate::NoTransitions => {
+                self.decide_catch_all::<E, OtherC>(event, owned_event, &mut None)
+            }
+            ProcessResultSubstate::GuardErr(ge) => return Err(ProcessResult::GuardErr(ge)),
+            ProcessResultSubstate::Conflict => return Err(ProcessResult::Conflict),
+            ProcessResultSubstate::OwnedEventRequired => {
+                return Err(ProcessResult::OwnedEventRequired)
+            }
+            ProcessResultSubstate::MustLeaveState => {
+                self.decide::<E, OtherTR, OtherC>(event, owned_event, &mut None)
+            }
         };
-        let result = self.transitions.hlist.process(
-            &mut self.current,
-            &mut self.state,
-            &event,
-            &mut self.vertexes,
-        );
+        decided
+            .map(|(chosen, _)| chosen)
+            .map_err(ProcessResult::from)
+    }
+
+    /// Probe transitions from the current vertex, without the sub state machine. Returns the
+    /// chosen transition or the reason why there is nothing to fire. Checked transitions are
+    /// collected into `trace`, if it is `Some`.
+    fn decide<'e, E, OtherTR, OtherC>(
+        &self,
+        event: &'e E::Of<'e>,
+        owned_event: bool,
+        trace: &mut Option<Trace<GErr>>,
+    ) -> Result<(Chosen, TransitionName), ProcessError<GErr>>
+    where
+        E: EventKind,
+        Transitions: ITransition<C, State, E, C, Vertexes, Answer, GErr, OtherTR>,
+        C: ProcessByForallTransitions<FAllTransitions, State, E, Vertexes, Answer, C, GErr, OtherC>,
+    {
+        let mut probe = Probe::new().owned_event(owned_event).traced(trace.take());
+        self.transitions
+            .hlist
+            .probe(&self.current, event, &mut probe);
+        let specific = probe.position();
+        self.current
+            .probe_by(&self.forall_transitions, event, &mut probe);
+        *trace = probe.take_trace();
+
+        match probe.decide() {
+            Decision::Fire(position, transition) if position < specific => {
+                Ok((Chosen::Transitions(position), transition))
+            }
+            Decision::Fire(position, transition) => {
+                Ok((Chosen::Forall(position - specific), transition))
+            }
+            Decision::Conflict => Err(ProcessError::Conflict),
+            Decision::OwnedEventRequired => Err(ProcessError::OwnedEventRequired),
+            Decision::Ignored => Err(ProcessError::Ignored),
+            Decision::Deferred => Err(ProcessError::Deferred),
+            Decision::GuardErr(ge) => Err(ProcessError::GuardErr(ge)),
+            Decision::Nothing => self.decide_catch_all::<E, OtherC>(event, owned_event, trace),
+        }
+    }
+
+    /// Probe catch-all transitions from the current vertex. Used when neither transitions from
+    /// the vertex nor its sub state machine match the event.
+    fn decide_catch_all<'e, E, OtherC>(
+        &self,
+        event: &'e E::Of<'e>,
+        owned_event: bool,
+        trace: &mut Option<Trace<GErr>>,
+    ) -> Result<(Chosen, TransitionName), ProcessError<GErr>>
+    where
+        E: EventKind,
+        C: ProcessByForallTransitions<FAllTransitions, State, E, Vertexes, Answer, C, GErr, OtherC>,
+    {
+        let mut probe = Probe::catch_all()
+            .owned_event(owned_event)
+            .traced(trace.take());
+        self.current
+            .probe_by(&self.forall_transitions, event, &mut probe);
+        *trace = probe.take_trace();
+        match probe.decide() {
+            Decision::Fire(position, transition) => Ok((Chosen::CatchAll(position), transition)),
+            Decision::Conflict => Err(ProcessError::Conflict),
+            Decision::GuardErr(ge) => Err(ProcessError::GuardErr(ge)),
+            Decision::OwnedEventRequired => Err(ProcessError::OwnedEventRequired),
+            Decision::Ignored | Decision::Deferred | Decision::Nothing => {
+                Err(ProcessError::NoTransitions)
+            }
+        }
+    }
+
+    /// Fire the chosen transition.
+    fn fire<E, OtherTR, OtherC>(
+        &mut self,
+        chosen: Chosen,
+        event: FireEvent<'_, '_, E>,
+    ) -> ProcessResultInner<(Answer, C), GErr>
+    where
+        E: EventKind,
+        Transitions: ITransition<C, State, E, C, Vertexes, Answer, GErr, OtherTR>,
+        C: ProcessByForallTransitions<FAllTransitions, State, E, Vertexes, Answer, C, GErr, OtherC>,
+    {
+        match chosen {
+            Chosen::Transitions(position) => self.transitions.hlist.fire(
+                &mut self.current,
+                &mut self.state,
+                event,
+                &mut self.vertexes,
+                &mut { position },
+            ),
+            Chosen::Forall(position) | Chosen::CatchAll(position) => self.current.fire_by(
+                &mut self.forall_transitions,
+                &mut self.state,
+                event,
+                &mut self.vertexes,
+                &mut { position },
+            ),
+        }
+    }
+
+    /// Move to the target vertex of the fired transition.
+    fn complete(
+        &mut self,
+        result: ProcessResultInner<(Answer, C), GErr>,
+    ) -> ProcessResult<Answer, GErr> {
         match result {
-            HandledAndProcessEnd((answer, target)) => {
+            ProcessResultInner::HandledAndProcessEnd((answer, target)) => {
                 self.current = target;
                 ProcessResult::Handled(answer)
             }
-            HandledAndProcessNext => ProcessEvent::process(self, event),
-            GuardErr(ge) => ProcessResult::GuardErr(ge),
-            EventTypeNotSatisfy | NoTransitions => {
-                match self.current.process_by(
-                    &mut self.forall_transitions,
-                    &mut self.state,
-                    event,
-                    &mut self.vertexes,
-                ) {
-                    HandledAndProcessNext => ProcessEvent::process(self, event),
-                    HandledAndProcessEnd((answer, target)) => {
-                        self.current = target;
-                        ProcessResult::Handled(answer)
+            result => result.map(|(answer, _)| answer).into(),
+        }
+    }
+}
+
+impl<
+        C,
+        State,
+        Vertexes,
+        VertHandlers,
+        Transitions,
+        FAllTransitions,
+        E: EventKind,
+        OtherTR,
+        Answer,
+        GErr,
+        OtherVH,
+        OtherC,
+    > ProcessEventKind<E, Answer, GErr, (OtherTR, OtherVH, OtherC)>
+    for StateMachine<
+        C,
+        State,
+        Vertexes,
+        VertHandlers,
+        HMap<Transitions>,
+        FAllTransitions,
+        Answer,
+        GErr,
+    >
+where
+    Transitions: ITransition<C, State, E, C, Vertexes, Answer, GErr, OtherTR>,
+    VertHandlers: VertexHandler<Vertexes, C, E, Answer, GErr, OtherVH>,
+    C: ProcessByForallTransitions<FAllTransitions, State, E, Vertexes, Answer, C, GErr, OtherC>
+        + IsTerminated
+        + VertexName,
+{
+    fn process_kind<'e>(&mut self, event: &'e E::Of<'e>) -> ProcessResult<Answer, GErr> {
+        let chosen = match self.choose::<E, OtherTR, OtherVH, OtherC>(event, false) {
+            Ok(chosen) => chosen,
+            Err(result) => return result,
+        };
+        match self.fire::<E, OtherTR, OtherC>(chosen, FireEvent::Borrowed(event)) {
+            ProcessResultInner::HandledAndProcessNext => self.process_kind(event),
+            result => self.complete(result),
+        }
+    }
+
+    fn can_process_kind<'e>(
+        &self,
+        event: &'e E::Of<'e>,
+    ) -> Result<TransitionInfo, ProcessError<GErr>> {
+        if self.current.is_terminated() {
+            return Err(ProcessError::Terminated);
+        }
+        let (chosen, transition) =
+            match self
+                .vertices_handlers
+                .probe(&self.vertexes, &self.current, event)
+            {
+                Some(Err(ProcessError::NoTransitions)) => {
+                    self.decide_catch_all::<E, OtherC>(event, false, &mut None)?
+                }
+                Some(result) => return result,
+                None => self.decide::<E, OtherTR, OtherC>(event, false, &mut None)?,
+            };
+        Ok(chosen.info(transition))
+    }
+
+    fn explain_kind<'e>(&self, event: &'e E::Of<'e>) -> Explanation<GErr>
+    where
+        GErr: Clone,
+    {
+        let mut explanation = Explanation {
+            event: std::any::type_name::<E::Of<'e>>(),
+            vertex: self.current.vertex_name(),
+            sub_machine: None,
+            candidates: Vec::new(),
+            result: Err(ProcessError::Terminated),
+        };
+        if self.current.is_terminated() {
+            return explanation;
+        }
+        explanation.sub_machine =
+            self.vertices_handlers
+                .probe(&self.vertexes, &self.current, event);
+        let mut trace = Some(Trace::new());
+        let decided = match &explanation.sub_machine {
+            Some(Err(ProcessError::NoTransitions)) => {
+                self.decide_catch_all::<E, OtherC>(event, false, &mut trace)
+            }
+            Some(result) => {
+                explanation.result = result.clone();
+                return explanation;
+            }
+            None => self.decide::<E, OtherTR, OtherC>(event, false, &mut trace),
+        };
+        explanation.result = decided.map(|(chosen, transition)| chosen.info(transition));
+        explanation.candidates = trace.map(|trace| trace.candidates).unwrap_or_default();
+        explanation
+    }
+}
+
+impl<
+        C,
+        State,
+        Vertexes,
+        VertHandlers,
+        Transitions,
+        FAllTransitions,
+        E: EventKind,
+        OtherTR,
+        Answer,
+        GErr,
+        OtherVH,
+        OtherC,
+    > ProcessEventDetailed<E, Answer, GErr, (OtherTR, OtherVH, OtherC)>
+    for StateMachine<
+        C,
+        State,
+        Vertexes,
+        VertHandlers,
+        HMap<Transitions>,
+        FAllTransitions,
+        Answer,
+        GErr,
+    >
+where
+    Transitions: ITransition<C, State, E, C, Vertexes, Answer, GErr, OtherTR>,
+    VertHandlers: VertexHandler<Vertexes, C, E, Answer, GErr, OtherVH>,
+    C: ProcessByForallTransitions<FAllTransitions, State, E, Vertexes, Answer, C, GErr, OtherC>
+        + IsTerminated
+        + VertexName,
+{
+    fn process_kind_detailed<'e>(&mut self, event: &'e E::Of<'e>) -> ProcessDetails<Answer, GErr> {
+        let source = self.current.vertex_name();
+        let (result, kind) = match self.choose::<E, OtherTR, OtherVH, OtherC>(event, false) {
+            Ok(chosen) => {
+                let kind = chosen.kind();
+                match self.fire::<E, OtherTR, OtherC>(chosen, FireEvent::Borrowed(event)) {
+                    ProcessResultInner::HandledAndProcessNext => {
+                        return self.process_kind_detailed(event)
                     }
-                    GuardErr(ge) => ProcessResult::GuardErr(ge),
-                    EventTypeNotSatisfy | NoTransitions => ProcessResult::NoTransitions,
+                    result => (self.complete(result), Some(kind)),
                 }
             }
+            Err(result @ ProcessResult::Handled(_)) => (result, Some(TransitionKind::SubMachine)),
+            Err(result) => (result, None),
+        };
+        let target = self.current.vertex_name();
+        let kind = match kind {
+            Some(TransitionKind::Normal) if source == target => Some(TransitionKind::Loop),
+            kind => kind,
+        };
+        ProcessDetails {
+            guard: GuardOutcome::of(&result),
+            result,
+            event: std::any::type_name::<E::Of<'e>>(),
+            source,
+            target,
+            kind,
         }
     }
 }
+
+impl<
+        C,
+        State,
+        Vertexes,
+        VertHandlers,
+        Transitions,
+        FAllTransitions,
+        E: 'static,
+        OtherTR,
+        Answer,
+        GErr,
+        OtherVH,
+        OtherC,
+    > ProcessOwnedEvent<E, Answer, GErr, (OtherTR, OtherVH, OtherC)>
+    for StateMachine<
+        C,
+        State,
+        Vertexes,
+        VertHandlers,
+        HMap<Transitions>,
+        FAllTransitions,
+        Answer,
+        GErr,
+    >
+where
+    Transitions: ITransition<C, State, Static<E>, C, Vertexes, Answer, GErr, OtherTR>,
+    VertHandlers: VertexHandler<Vertexes, C, Static<E>, Answer, GErr, OtherVH>,
+    C: ProcessByForallTransitions<
+            FAllTransitions,
+            State,
+            Static<E>,
+            Vertexes,
+            Answer,
+            C,
+            GErr,
+            OtherC,
+        > + IsTerminated,
+{
+    fn process_owned(&mut self, event: E) -> ProcessResult<Answer, GErr> {
+        let chosen = match self.choose::<Static<E>, OtherTR, OtherVH, OtherC>(&event, true) {
+            Ok(chosen) => chosen,
+            Err(result) => return result,
+        };
+        let result =
+            self.fire::<Static<E>, OtherTR, OtherC>(chosen, FireEvent::Owned(&mut Some(event)));
+        self.complete(result)
+    }
+}
@@ -1,26 +1,407 @@
-use crate::action::ActionLoop;
+//! Transitions and the order in which they are chosen.
+//!
+//! Processing of an event is split in two phases. At first `StateMachine` probes all transitions
+//! that can be started from the current vertex: it checks `Trigger` and calls `Guard`, but does not
+//! call `Action`. Then it fires the only transition that was chosen.
+//!
+//! The transition is chosen in the following order:
+//! 1. If the current vertex is a sub state machine, the event is processed by it. The parent state
+//!    machine does not see the event until the sub state machine comes to a final vertex,
+//!    except for catch-all transitions from the sub state machine vertex: they are probed (as in
+//!    step 7) if the sub state machine has no transitions for the event.
+//! 2. An enabled transition (its `Trigger` accepts the event and its `Guard` returns `Ok`) with the
+//!    highest priority wins. Priority is `0` by default and can be set by wrapping the action into
+//!    `action::Prioritized` or by `priority(N)` before the line in `state_machine!`.
+//! 3. If priorities are equal, transitions from the current vertex and loops win over forall
+//!    transitions.
+//! 4. If there are still several enabled transitions, `StateMachine::process` returns
+//!    `ProcessResult::Conflict` and does nothing. So the order of lines never changes behaviour.
+//! 5. If no transition is enabled, but the current vertex ignores the event explicitly (by the
+//!    `ignore:` section of `state_machine!`), `ProcessResult::Ignored` is returned. If it defers
+//!    the event (by the `defer:` section), `ProcessResult::Deferred` is returned.
+//! 6. If no transition is enabled, but some of them are rejected by `Guard`, the error of the
+//!    rejected transition with the highest rank (by the same rules) is returned.
+//! 7. If no transition accepts the event at all, catch-all transitions (`Source + _`) are probed by
+//!    the same rules.
+//!
+//! `StateMachine::can_process` and `StateMachine::explain` choose the transition the same way, but
+//! never fire it. `explain` also returns every transition that was checked.
+//!
+//! `Transition` borrows its `Source` and `Target` vertices at the same time, so they must be
+//! different types. A transition from a vertex to itself is a loop:
+//!
+//! ```compile_fail
+//! use umlsm::vertex::{EntryVertex, ExitVertex, InitialPseudoState};
+//!
+//! struct Idle;
+//! impl EntryVertex for Idle {}
+//! impl ExitVertex for Idle {}
+//! struct Tick;
+//!
+//! // `Idle + Tick => Idle` does not compile, write `loop: Idle + Tick;` instead.
+//! let sm = umlsm::state_machine!(
+//!     state = (), err = (),
+//!     [Idle],
+//!
+//!     InitialPseudoState + () => Idle,
+//!     Idle               + Tick => Idle;
+//! );
+//! ```
+//!
+//! Vertices are compared by their indices in the list of vertices, which are resolved at compile
+//! time: a forall transition skips its `Target` and the vertices from its `except` list, and a
+//! catch-all transition applies only to its `Source`. So every type in an `except` list must be a
+//! vertex of the state machine.
+//!
+//! Events are not resolved at compile time: `process` accepts an event of any type, so a
+//! transition matches it to its trigger at runtime by `trigger::cast`, which compares `TypeId`s.
+//! Dispatch is free of `unsafe` code, but not of `TypeId`.
+
+use crate::action::{ActionLoop, ForallAction, InternalAction, OwnedAction, OwnedActionLoop};
 use crate::hmap::HMapNil;
-use crate::process_result::ProcessResultInner;
-use crate::utils::SelectorPointer;
-use crate::vertex::{EntryVertex, ExitVertex, TerminationPseudoState};
+use crate::process_result::{
+    Candidate, CandidateOutcome, GuardRejection, ProcessResultInner, TransitionName,
+};
+use crate::trigger::{EventKind, Single, Trigger};
+use crate::utils::{
+    ContainsIndex, PairSelector, SameIndex, TypeList, VertexIndices, VertexSelector,
+};
+use crate::vertex::{EntryPolicy, EntryVertex, ExitPolicy, ExitVertex, TerminationPseudoState};
 use crate::{Action, Guard};
 use frunk::coproduct::{CNil, CoprodInjector};
-use frunk::hlist::Selector;
 use frunk::{Coproduct, HCons, HNil};
-use std::any::TypeId;
+use std::any::Any;
 use std::marker::PhantomData;
 
-pub struct Transition<Source, Ctx, Event, Action, Guard, Target, Answer, GErr> {
-    action: Action,
-    guard: Guard,
-    phantom: PhantomData<(Source, Ctx, Event, Target, Answer, GErr)>,
+/// Kind of transition. If priorities are equal, the greater kind wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    CatchAll,
+    Forall,
+    Specific,
+}
+
+/// Result of probing all transitions from the current vertex.
+pub enum Decision<GErr> {
+    /// Fire the transition with this position.
+    Fire(usize, TransitionName),
+    /// Several transitions with the same rank are enabled.
+    Conflict,
+    /// No transition is enabled, but the current vertex ignores the event.
+    Ignored,
+    /// No transition is enabled, but the current vertex defers the event.
+    Deferred,
+    /// No transition is enabled, the best rejected one returns this error.
+    GuardErr(GuardRejection<GErr>),
+    /// No transition is enabled, but the trigger of a transition with an owned action accepts the
+    /// event, which is processed by reference.
+    OwnedEventRequired,
+    /// No transition accepts the event.
+    Nothing,
+}
+
+/// Collector of candidates, filled by `ITransition::probe`.
+///
+/// Every transition takes exactly one position, both in `probe` and in `fire`, so the position of
+/// the chosen transition is the same in both phases.
+pub struct Probe<GErr> {
+    position: usize,
+    catch_all: bool,
+    catch_all_scope: bool,
+    enabled: Option<((i32, Kind), usize, TransitionName)>,
+    conflict: bool,
+    rejected: Option<((i32, Kind), GuardRejection<GErr>)>,
+    ignored: bool,
+    deferred: bool,
+    owned_event: bool,
+    owned_event_required: bool,
+    trace: Option<Trace<GErr>>,
+}
+
+/// Candidates that are offered to `Probe`, collected for `StateMachine::explain`.
+pub struct Trace<GErr> {
+    pub candidates: Vec<Candidate<GErr>>,
+    clone: fn(&GErr) -> GErr,
+}
+
+impl<GErr: Clone> Trace<GErr> {
+    pub fn new() -> Self {
+        Trace {
+            candidates: Vec::new(),
+            clone: GErr::clone,
+        }
+    }
+}
+
+impl<GErr: Clone> Default for Trace<GErr> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl<Source, Ctx, Event, ActionT, GuardT, GErr, Target, Answer>
-    Transition<Source, Ctx, Event, ActionT, GuardT, Target, Answer, GErr>
+impl<GErr> Probe<GErr> {
+    /// Probe for all transitions except catch-all ones.
+    pub fn new() -> Self {
+        Probe {
+            position: 0,
+            catch_all: false,
+            catch_all_scope: false,
+            enabled: None,
+            conflict: false,
+            rejected: None,
+            ignored: false,
+            deferred: false,
+            owned_event: false,
+            owned_event_required: false,
+            trace: None,
+        }
+    }
+
+    /// Probe for catch-all transitions only.
+    pub fn catch_all() -> Self {
+        Probe {
+            catch_all: true,
+            ..Probe::new()
+        }
+    }
+
+    /// Mark that the event is processed by value, so transitions with owned actions can be chosen.
+    pub fn owned_event(self, owned_event: bool) -> Self {
+        Probe {
+            owned_event,
+            ..self
+        }
+    }
+
+    /// Collect all offered candidates into `trace`.
+    pub fn traced(self, trace: Option<Trace<GErr>>) -> Self {
+        Probe { trace, ..self }
+    }
+
+    /// Take the collected candidates.
+    pub fn take_trace(&mut self) -> Option<Trace<GErr>> {
+        self.trace.take()
+    }
+
+    /// Check that the event is processed by value.
+    pub fn is_owned_event(&self) -> bool {
+        self.owned_event
+    }
+
+    /// Count of positions that are taken.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Take the next position. `outcome` is `None` if the trigger does not accept the event and the
+    /// result of `Guard::check_each` otherwise.
+    pub fn offer(
+        &mut self,
+        priority: i32,
+        kind: Kind,
+        transition: TransitionName,
+        outcome: Option<Result<(), (usize, Option<&'static str>, GErr)>>,
+    ) {
+        let position = self.position;
+        self.position += 1;
+
+        let kind = if self.catch_all_scope {
+            Kind::CatchAll
+        } else {
+            kind
+        };
+        if (kind == Kind::CatchAll) != self.catch_all {
+            return;
+        }
+        if let Some(trace) = &mut self.trace {
+            let outcome = match &outcome {
+                None => CandidateOutcome::EventMismatch,
+                Some(Ok(())) => CandidateOutcome::Accepted,
+                Some(Err((guard_index, guard_name, error))) => {
+                    CandidateOutcome::Rejected(GuardRejection {
+                        transition,
+                        guard_index: *guard_index,
+                        guard_name: *guard_name,
+                        error: (trace.clone)(error),
+                    })
+                }
+            };
+            trace.candidates.push(Candidate {
+                transition,
+                kind,
+                priority,
+                outcome,
+            });
+        }
+        let rank = (priority, kind);
+        match outcome {
+            None => {}
+            Some(Ok(())) => match self.enabled {
+                Some((best, ..)) if best > rank => {}
+                Some((best, ..)) if best == rank => self.conflict = true,
+                _ => {
+                    self.enabled = Some((rank, position, transition));
+                    self.conflict = false;
+                }
+            },
+            Some(Err((guard_index, guard_name, error))) => match self.rejected {
+                Some((best, _)) if best >= rank => {}
+                _ => {
+                    let rejection = GuardRejection {
+                        transition,
+                        guard_index,
+                        guard_name,
+                        error,
+                    };
+                    self.rejected = Some((rank, rejection))
+                }
+            },
+        }
+    }
+
+    /// Take the next position for a transition with an owned action. The candidate is offered only
+    /// if the event is processed by value. Otherwise, if the trigger accepts the event, it only
+    /// marks that the event must be processed by value.
+    pub fn offer_owned(
+        &mut self,
+        priority: i32,
+        kind: Kind,
+        transition: TransitionName,
+        outcome: Option<Result<(), (usize, Option<&'static str>, GErr)>>,
+    ) {
+        if self.owned_event {
+            return self.offer(priority, kind, transition, outcome);
+        }
+        self.position += 1;
+        if !self.catch_all && outcome.is_some() {
+            self.owned_event_required = true;
+        }
+    }
+
+    /// Take the next position without offering a candidate.
+    pub fn skip(&mut self) {
+        self.position += 1;
+    }
+
+    /// Take the next position and mark that the current vertex ignores the event.
+    pub fn ignore(&mut self) {
+        self.position += 1;
+        if !self.catch_all {
+            self.ignored = true;
+        }
+    }
+
+    /// Take the next position and mark that the current vertex defers the event.
+    pub fn defer(&mut self) {
+        self.position += 1;
+        if !self.catch_all {
+            self.deferred = true;
+        }
+    }
+
+    /// Treat all candidates offered by `f` as catch-all ones.
+    pub fn catch_all_scope(&mut self, f: impl FnOnce(&mut Self)) {
+        self.catch_all_scope = true;
+        f(self);
+        self.catch_all_scope = false;
+    }
+
+    pub fn decide(self) -> Decision<GErr> {
+        match (self.enabled, self.rejected) {
+            (Some(_), _) if self.conflict => Decision::Conflict,
+            (Some((_, position, transition)), _) => Decision::Fire(position, transition),
+            (None, _) if self.owned_event_required => Decision::OwnedEventRequired,
+            (None, _) if self.ignored => Decision::Ignored,
+            (None, _) if self.deferred => Decision::Deferred,
+            (None, Some((_, e))) => Decision::GuardErr(e),
+            (None, None) => Decision::Nothing,
+        }
+    }
+}
+
+impl<GErr> Default for Probe<GErr> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The event that is given to `ITransition::fire`.
+///
+/// - `Borrowed` - the event is processed by reference.
+/// - `Owned` - the event is processed by value. `slot` is `Option` with the event, the transition
+///   with an owned action takes the event from it.
+pub enum FireEvent<'e, 's, K: EventKind> {
+    Borrowed(&'e K::Of<'e>),
+    Owned(&'s mut dyn Any),
+}
+
+impl<'e, K: EventKind> FireEvent<'e, '_, K> {
+    /// Make a copy of the event that can be given to the next transition.
+    pub fn reborrow(&mut self) -> FireEvent<'e, '_, K> {
+        match self {
+            FireEvent::Borrowed(event) => FireEvent::Borrowed(*event),
+            FireEvent::Owned(slot) => FireEvent::Owned(&mut **slot),
+        }
+    }
+
+    /// Call `f` with the view of the event if `Trig` accepts it.
+    pub fn with_matched<Trig: Trigger, R>(
+        self,
+        f: impl for<'a> FnOnce(&Trig::Event<'a>) -> R,
+    ) -> Option<R> {
+        match self {
+            FireEvent::Borrowed(event) => Trig::with_matched::<K, _>(event, f),
+            FireEvent::Owned(slot) => Trig::with_matched::<K, _>(K::from_owned(slot)?, f),
+        }
+    }
+
+    /// Take the event of type `T` if it is processed by value.
+    pub fn take<T: 'static>(self) -> Option<T> {
+        match self {
+            FireEvent::Borrowed(_) => None,
+            FireEvent::Owned(slot) => slot.downcast_mut::<Option<T>>()?.take(),
+        }
+    }
+}
+
+/// Return `true` if `position` is reached, otherwise move to the next one.
+///
+/// The transition at the reached position is the chosen one. If it cannot fire, e.g. the slot of
+/// its vertex is empty, it returns `NoTransitions` instead of `EventTypeNotSatisfy`, so the
+/// following transitions are not tried.
+fn reached(position: &mut usize) -> bool {
+    if *position == 0 {
+        true
+    } else {
+        *position -= 1;
+        false
+    }
+}
+
+pub struct Transition<Source, Ctx, Trig, Action, Guard, Target, Answer, GErr> {
+    pub(crate) action: Action,
+    pub(crate) guard: Guard,
+    phantom: PhantomData<(Source, Ctx, Trig, Target, Answer, GErr)>,
+}
+
+impl<Source, Ctx, Trig, Action: Clone, Guard: Clone, Target, Answer, GErr> Clone
+    for Transition<Source, Ctx, Trig, Action, Guard, Target, Answer, GErr>
+{
+    fn clone(&self) -> Self {
+        Transition {
+            action: self.action.clone(),
+            guard: self.guard.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Source, Ctx, Trig, ActionT, GuardT, GErr, Target, Answer>
+    Transition<Source, Ctx, Trig, ActionT, GuardT, Target, Answer, GErr>
 where
-    ActionT: Action<Source, Ctx, Event, Target, Answer>,
-    GuardT: Guard<Event, GErr>,
+    Trig: Trigger,
+    ActionT: for<'a> Action<Source, Ctx, Trig::Event<'a>, Target, Answer>,
+    GuardT: for<'a> Guard<Trig::Event<'a>, GErr>,
 {
     pub fn new(action: ActionT, guard: GuardT) -> Self {
         Transition {
@@ -31,30 +412,22 @@ where
     }
 }
 
-pub trait ITransition<Source, Ctx, Event, Target, Vertexes, Answer, GErr, Other> {
-    fn process(
+pub trait ITransition<Source, Ctx, Event: EventKind, Target, Vertexes, Answer, GErr, Other> {
+    /// Offer this transition to `probe`. `Action` must not be called here.
+    fn probe<'e>(&self, source: &Source, event: &'e Event::Of<'e>, probe: &mut Probe<GErr>);
+
+    /// Fire the transition with the specified position, which was chosen by `probe`.
+    fn fire<'e>(
         &mut self,
         source: &mut Source,
         ctx: &mut Ctx,
-        event: &Event,
+        event: FireEvent<'e, '_, Event>,
         vertexes: &mut Vertexes,
+        position: &mut usize,
     ) -> ProcessResultInner<(Answer, Target), GErr>;
 }
 
-impl<
-        Source,
-        Ctx,
-        TransEvent,
-        Event,
-        ActionT,
-        GuardT,
-        Target,
-        Vertexes,
-        Answer,
-        GErr,
-        Idx1,
-        Idx2,
-    >
+impl<Source, Ctx, Trig, Event, ActionT, GuardT, Target, Vertexes, Answer, GErr, Indices>
     ITransition<
         PhantomData<Source>,
         Ctx,
@@ -63,62 +436,78 @@ impl<
         Vertexes,
         Answer,
         GErr,
-        (Idx1, Idx2),
-    > for Transition<Source, Ctx, TransEvent, ActionT, GuardT, Target, Answer, GErr>
+        Indices,
+    > for Transition<Source, Ctx, Trig, ActionT, GuardT, Target, Answer, GErr>
 where
-    Vertexes: SelectorPointer<Source, Idx1> + SelectorPointer<Target, Idx2>,
+    Vertexes: PairSelector<Source, Target, Indices>,
+    Vertexes::PolicyA: ExitPolicy<Source>,
+    Vertexes::PolicyB: for<'a> EntryPolicy<Target, Ctx, Trig::Event<'a>>,
     Source: ExitVertex,
     Target: EntryVertex,
-    ActionT: Action<Source, Ctx, TransEvent, Target, Answer>,
-    GuardT: Guard<TransEvent, GErr>,
+    Trig: Trigger,
+    ActionT: for<'a> Action<Source, Ctx, Trig::Event<'a>, Target, Answer>,
+    GuardT: for<'a> Guard<Trig::Event<'a>, GErr>,
     Source: 'static,
     Target: 'static,
-    Event: 'static,
-    TransEvent: 'static,
+    Event: EventKind,
 {
-    fn process(
+    fn probe<'e>(
+        &self,
+        _: &PhantomData<Source>,
+        event: &'e Event::Of<'e>,
+        probe: &mut Probe<GErr>,
+    ) {
+        let outcome = Trig::with_matched::<Event, _>(event, |event| self.guard.check_each(event));
+        let transition = TransitionName::of::<Source, Event::Of<'e>, Target>();
+        let priority =
+            Action::<Source, Ctx, Trig::Event<'_>, Target, Answer>::priority(&self.action);
+        probe.offer(priority, Kind::Specific, transition, outcome);
+    }
+
+    fn fire<'e>(
         &mut self,
         _: &mut PhantomData<Source>,
         ctx: &mut Ctx,
-        event: &Event,
+        event: FireEvent<'e, '_, Event>,
         vertexes: &mut Vertexes,
+        position: &mut usize,
     ) -> ProcessResultInner<(Answer, PhantomData<Target>), GErr> {
         use ProcessResultInner::*;
-        if TypeId::of::<Event>() == TypeId::of::<TransEvent>() {
-            let event = unsafe { &*(event as *const Event as *const TransEvent) };
-
-            match self.guard.check(event) {
-                Ok(_) => {
-                    if TypeId::of::<Source>() == TypeId::of::<Target>() {
-                        panic!("Transition must not have the same Source and Target vertices.");
-                    }
-                    let source =
-                        unsafe { &mut *(SelectorPointer::<Source, _>::get_mut_ptr(vertexes)) };
-                    let target =
-                        unsafe { &mut *(SelectorPointer::<Target, _>::get_mut_ptr(vertexes)) };
-                    let answer = self.action.trigger(source, ctx, &event, target);
-
-                    source.exit();
-                    target.entry();
-                    HandledAndProcessEnd((answer, PhantomData))
-                }
-                Err(e) => GuardErr(e),
-            }
-        } else {
-            ProcessResultInner::EventTypeNotSatisfy
+        if !reached(position) {
+            return EventTypeNotSatisfy;
         }
+        let Transition { action, .. } = self;
+        event
+            .with_matched::<Trig, _>(|event| {
+                let (source, target) = vertexes.get_pair_mut();
+                let Some(source_vertex) = source.get_mut() else {
+                    return NoTransitions;
+                };
+                let Some(target) = target.enter(ctx, event) else {
+                    return NoTransitions;
+                };
+                let answer = action.trigger(source_vertex, ctx, event, target);
+
+                source.leave();
+                target.entry();
+                HandledAndProcessEnd((answer, PhantomData))
+            })
+            .unwrap_or(NoTransitions)
     }
 }
 
-impl<Source, Ctx, Event, Vertexes, Target, Answer, GErr>
+impl<Source, Ctx, Event: EventKind, Vertexes, Target, Answer, GErr>
     ITransition<Source, Ctx, Event, Target, Vertexes, Answer, GErr, ()> for HNil
 {
-    fn process(
+    fn probe<'e>(&self, _: &Source, _: &'e Event::Of<'e>, _: &mut Probe<GErr>) {}
+
+    fn fire<'e>(
         &mut self,
         _: &mut Source,
         _: &mut Ctx,
-        _: &Event,
+        _: FireEvent<'e, '_, Event>,
         _: &mut Vertexes,
+        _: &mut usize,
     ) -> ProcessResultInner<(Answer, Target), GErr> {
         ProcessResultInner::NoTransitions
     }
@@ -127,7 +516,7 @@ impl<Source, Ctx, Event, Vertexes, Target, Answer, GErr>
 impl<
         Source,
         Ctx,
-        Event,
+        Event: EventKind,
         TargetUnit,
         Target,
         Vertexes,
@@ -163,36 +552,52 @@ where
     Target: CoprodInjector<PhantomData<TargetUnit>, Indices>,
     Rest: ITransition<PhantomData<Source>, Ctx, Event, Target, Vertexes, Answer, GErr, Other>,
 {
-    fn process(
+    fn probe<'e>(
+        &self,
+        source: &PhantomData<Source>,
+        event: &'e Event::Of<'e>,
+        probe: &mut Probe<GErr>,
+    ) {
+        self.head.probe(source, event, probe);
+        self.tail.probe(source, event, probe);
+    }
+
+    fn fire<'e>(
         &mut self,
         source: &mut PhantomData<Source>,
         ctx: &mut Ctx,
-        event: &Event,
+        mut event: FireEvent<'e, '_, Event>,
         vertexes: &mut Vertexes,
+        position: &mut usize,
     ) -> ProcessResultInner<(Answer, Target), GErr> {
         let res = self
             .head
-            .process(source, ctx, unsafe { std::mem::transmute(event) }, vertexes)
+            .fire(source, ctx, event.reborrow(), vertexes, position)
             .map(|(a, t)| (a, Target::inject(t)));
 
         match res {
             ProcessResultInner::EventTypeNotSatisfy => {
-                self.tail.process(source, ctx, event, vertexes)
+                self.tail.fire(source, ctx, event, vertexes, position)
             }
             _ => res,
         }
     }
 }
 
-impl<Ctx, Event, Target, Vertexes, Answer, GErr>
+impl<Ctx, Event: EventKind, Target, Vertexes, Answer, GErr>
     ITransition<CNil, Ctx, Event, Target, Vertexes, Answer, GErr, ()> for HMapNil
 {
-    fn process(
+    fn probe<'e>(&self, source: &CNil, _: &'e Event::Of<'e>, _: &mut Probe<GErr>) {
+        match *source {}
+    }
+
+    fn fire<'e>(
         &mut self,
         source: &mut CNil,
         _: &mut Ctx,
-        _: &Event,
+        _: FireEvent<'e, '_, Event>,
         _: &mut Vertexes,
+        _: &mut usize,
     ) -> ProcessResultInner<(Answer, Target), GErr> {
         match *source {}
     }
@@ -203,7 +608,7 @@ impl<
         SourceRest,
         Trans,
         Ctx,
-        Event,
+        Event: EventKind,
         Rest,
         OtherHM,
         OtherRest,
@@ -226,34 +631,60 @@ where
     Trans: ITransition<PhantomData<Source>, Ctx, Event, Target, Vertexes, Answer, GErr, OtherHM>,
     Rest: ITransition<SourceRest, Ctx, Event, Target, Vertexes, Answer, GErr, OtherRest>,
 {
-    fn process(
+    fn probe<'e>(
+        &self,
+        source: &Coproduct<PhantomData<Source>, SourceRest>,
+        event: &'e Event::Of<'e>,
+        probe: &mut Probe<GErr>,
+    ) {
+        match source {
+            Coproduct::Inl(l) => self.head.1.probe(l, event, probe),
+            Coproduct::Inr(r) => self.tail.probe(r, event, probe),
+        }
+    }
+
+    fn fire<'e>(
         &mut self,
         source: &mut Coproduct<PhantomData<Source>, SourceRest>,
         ctx: &mut Ctx,
-        event: &Event,
+        event: FireEvent<'e, '_, Event>,
         vertexes: &mut Vertexes,
+        position: &mut usize,
     ) -> ProcessResultInner<(Answer, Target), GErr> {
         match source {
-            Coproduct::Inl(l) => self.head.1.process(l, ctx, event, vertexes),
+            Coproduct::Inl(l) => self.head.1.fire(l, ctx, event, vertexes, position),
             Coproduct::Inr(r) => {
                 let HCons { head: _, tail } = self;
-                tail.process(r, ctx, event, vertexes)
+                tail.fire(r, ctx, event, vertexes, position)
             }
         }
     }
 }
 
-pub struct LoopTransition<Vertex, Ctx, Event, Action, Guard, Answer, GErr> {
-    action: Action,
-    guard: Guard,
-    phantom: PhantomData<(Vertex, Ctx, Event, Answer, GErr)>,
+pub struct LoopTransition<Vertex, Ctx, Trig, Action, Guard, Answer, GErr> {
+    pub(crate) action: Action,
+    pub(crate) guard: Guard,
+    phantom: PhantomData<(Vertex, Ctx, Trig, Answer, GErr)>,
 }
 
-impl<Vertex, Ctx, Event, ActionT, GuardT, GErr, Answer>
-    LoopTransition<Vertex, Ctx, Event, ActionT, GuardT, Answer, GErr>
+impl<Vertex, Ctx, Trig, Action: Clone, Guard: Clone, Answer, GErr> Clone
+    for LoopTransition<Vertex, Ctx, Trig, Action, Guard, Answer, GErr>
+{
+    fn clone(&self) -> Self {
+        LoopTransition {
+            action: self.action.clone(),
+            guard: self.guard.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Vertex, Ctx, Trig, ActionT, GuardT, GErr, Answer>
+    LoopTransition<Vertex, Ctx, Trig, ActionT, GuardT, Answer, GErr>
 where
-    ActionT: ActionLoop<Vertex, Ctx, Event, Answer>,
-    GuardT: Guard<Event, GErr>,
+    Trig: Trigger,
+    ActionT: for<'a> ActionLoop<Vertex, Ctx, Trig::Event<'a>, Answer>,
+    GuardT: for<'a> Guard<Trig::Event<'a>, GErr>,
 {
     pub fn new(action: ActionT, guard: GuardT) -> Self {
         LoopTransition {
@@ -264,53 +695,279 @@ where
     }
 }
 
-impl<Vertex, Ctx, TransEvent, Event, ActionT, GuardT, Vertexes, Answer, GErr, Idx1>
+impl<Vertex, Ctx, Trig, Event, ActionT, GuardT, Vertexes, Answer, GErr, Idx1>
     ITransition<PhantomData<Vertex>, Ctx, Event, PhantomData<Vertex>, Vertexes, Answer, GErr, Idx1>
-    for LoopTransition<Vertex, Ctx, TransEvent, ActionT, GuardT, Answer, GErr>
+    for LoopTransition<Vertex, Ctx, Trig, ActionT, GuardT, Answer, GErr>
 where
-    Vertexes: Selector<Vertex, Idx1>,
+    Vertexes: VertexSelector<Vertex, Idx1>,
     Vertex: ExitVertex + EntryVertex,
-    ActionT: ActionLoop<Vertex, Ctx, TransEvent, Answer>,
-    GuardT: Guard<TransEvent, GErr>,
-    Event: 'static,
-    TransEvent: 'static,
+    Trig: Trigger,
+    ActionT: for<'a> ActionLoop<Vertex, Ctx, Trig::Event<'a>, Answer>,
+    GuardT: for<'a> Guard<Trig::Event<'a>, GErr>,
+    Event: EventKind,
 {
-    fn process(
+    fn probe<'e>(
+        &self,
+        _: &PhantomData<Vertex>,
+        event: &'e Event::Of<'e>,
+        probe: &mut Probe<GErr>,
+    ) {
+        let outcome = Trig::with_matched::<Event, _>(event, |event| self.guard.check_each(event));
+        let transition = TransitionName::of::<Vertex, Event::Of<'e>, Vertex>();
+        let priority = ActionLoop::<Vertex, Ctx, Trig::Event<'_>, Answer>::priority(&self.action);
+        probe.offer(priority, Kind::Specific, transition, outcome);
+    }
+
+    fn fire<'e>(
         &mut self,
         _: &mut PhantomData<Vertex>,
         ctx: &mut Ctx,
-        event: &Event,
+        event: FireEvent<'e, '_, Event>,
         vertexes: &mut Vertexes,
+        position: &mut usize,
     ) -> ProcessResultInner<(Answer, PhantomData<Vertex>), GErr> {
         use ProcessResultInner::*;
-        if TypeId::of::<Event>() == TypeId::of::<TransEvent>() {
-            let event = unsafe { &*(event as *const Event as *const TransEvent) };
+        if !reached(position) {
+            return EventTypeNotSatisfy;
+        }
+        let LoopTransition { action, .. } = self;
+        event
+            .with_matched::<Trig, _>(|event| {
+                let Some(vertex) = vertexes.slot_mut().get_mut() else {
+                    return NoTransitions;
+                };
+                let answer = action.trigger(vertex, ctx, event);
 
-            match self.guard.check(event) {
-                Ok(_) => {
-                    let vertex = Selector::<Vertex, Idx1>::get_mut(vertexes);
-                    let answer = self.action.trigger(vertex, ctx, &event);
+                vertex.exit();
+                vertex.entry();
+                HandledAndProcessEnd((answer, PhantomData))
+            })
+            .unwrap_or(NoTransitions)
+    }
+}
 
-                    vertex.exit();
-                    vertex.entry();
-                    HandledAndProcessEnd((answer, PhantomData))
-                }
-                Err(e) => GuardErr(e),
-            }
-        } else {
-            ProcessResultInner::EventTypeNotSatisfy
+/// Transition that takes the event by value and gives it to `OwnedAction`.
+///
+/// It can be chosen only when the event is processed by `StateMachine::process_owned`.
+pub struct OwnedTransition<Source, Ctx, E, Action, Guard, Target, Answer, GErr> {
+    pub(crate) action: Action,
+    pub(crate) guard: Guard,
+    phantom: PhantomData<(Source, Ctx, E, Target, Answer, GErr)>,
+}
+
+impl<Source, Ctx, E, Action: Clone, Guard: Clone, Target, Answer, GErr> Clone
+    for OwnedTransition<Source, Ctx, E, Action, Guard, Target, Answer, GErr>
+{
+    fn clone(&self) -> Self {
+        OwnedTransition {
+            action: self.action.clone(),
+            guard: self.guard.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Source, Ctx, E, ActionT, GuardT, GErr, Target, Answer>
+    OwnedTransition<Source, Ctx, E, ActionT, GuardT, Target, Answer, GErr>
+where
+    ActionT: OwnedAction<Source, Ctx, E, Target, Answer>,
+    GuardT: Guard<E, GErr>,
+{
+    pub fn new(action: ActionT, guard: GuardT) -> Self {
+        OwnedTransition {
+            action,
+            guard,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Source, Ctx, E, Event, ActionT, GuardT, Target, Vertexes, Answer, GErr, Indices>
+    ITransition<
+        PhantomData<Source>,
+        Ctx,
+        Event,
+        PhantomData<Target>,
+        Vertexes,
+        Answer,
+        GErr,
+        Indices,
+    > for OwnedTransition<Source, Ctx, E, ActionT, GuardT, Target, Answer, GErr>
+where
+    Vertexes: PairSelector<Source, Target, Indices>,
+    Vertexes::PolicyA: ExitPolicy<Source>,
+    Vertexes::PolicyB: EntryPolicy<Target, Ctx, E>,
+    Source: ExitVertex,
+    Target: EntryVertex,
+    ActionT: OwnedAction<Source, Ctx, E, Target, Answer>,
+    GuardT: Guard<E, GErr>,
+    E: 'static,
+    Event: EventKind,
+{
+    fn probe<'e>(
+        &self,
+        _: &PhantomData<Source>,
+        event: &'e Event::Of<'e>,
+        probe: &mut Probe<GErr>,
+    ) {
+        let outcome =
+            Single::<E>::with_matched::<Event, _>(event, |event| self.guard.check_each(event));
+        let transition = TransitionName::of::<Source, Event::Of<'e>, Target>();
+        let priority = OwnedAction::<Source, Ctx, E, Target, Answer>::priority(&self.action);
+        probe.offer_owned(priority, Kind::Specific, transition, outcome);
+    }
+
+    fn fire<'e>(
+        &mut self,
+        _: &mut PhantomData<Source>,
+        ctx: &mut Ctx,
+        event: FireEvent<'e, '_, Event>,
+        vertexes: &mut Vertexes,
+        position: &mut usize,
+    ) -> ProcessResultInner<(Answer, PhantomData<Target>), GErr> {
+        use ProcessResultInner::*;
+        if !reached(position) {
+            return EventTypeNotSatisfy;
         }
+        let Some(event) = event.take::<E>() else {
+            return NoTransitions;
+        };
+        let (source, target) = vertexes.get_pair_mut();
+        let Some(source_vertex) = source.get_mut() else {
+            return NoTransitions;
+        };
+        let Some(target) = target.enter(ctx, &event) else {
+            return NoTransitions;
+        };
+        let answer = self.action.trigger(source_vertex, ctx, event, target);
+
+        source.leave();
+        target.entry();
+        HandledAndProcessEnd((answer, PhantomData))
     }
 }
 
-pub struct ForallTransition<Action, Guard> {
-    action: Action,
-    guard: Guard,
+/// Loop that takes the event by value and gives it to `OwnedActionLoop`.
+///
+/// It can be chosen only when the event is processed by `StateMachine::process_owned`.
+pub struct OwnedLoopTransition<Vertex, Ctx, E, Action, Guard, Answer, GErr> {
+    pub(crate) action: Action,
+    pub(crate) guard: Guard,
+    phantom: PhantomData<(Vertex, Ctx, E, Answer, GErr)>,
 }
 
-impl<Action, Guard> ForallTransition<Action, Guard> {
-    pub fn new(action: Action, guard: Guard) -> Self {
-        ForallTransition { action, guard }
+impl<Vertex, Ctx, E, Action: Clone, Guard: Clone, Answer, GErr> Clone
+    for OwnedLoopTransition<Vertex, Ctx, E, Action, Guard, Answer, GErr>
+{
+    fn clone(&self) -> Self {
+        OwnedLoopTransition {
+            action: self.action.clone(),
+            guard: self.guard.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Vertex, Ctx, E, ActionT, GuardT, GErr, Answer>
+    OwnedLoopTransition<Vertex, Ctx, E, ActionT, GuardT, Answer, GErr>
+where
+    ActionT: OwnedActionLoop<Vertex, Ctx, E, Answer>,
+    GuardT: Guard<E, GErr>,
+{
+    pub fn new(action: ActionT, guard: GuardT) -> Self {
+        OwnedLoopTransition {
+            action,
+            guard,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Vertex, Ctx, E, Event, ActionT, GuardT, Vertexes, Answer, GErr, Idx1>
+    ITransition<PhantomData<Vertex>, Ctx, Event, PhantomData<Vertex>, Vertexes, Answer, GErr, Idx1>
+    for OwnedLoopTransition<Vertex, Ctx, E, ActionT, GuardT, Answer, GErr>
+where
+    Vertexes: VertexSelector<Vertex, Idx1>,
+    Vertex: ExitVertex + EntryVertex,
+    ActionT: OwnedActionLoop<Vertex, Ctx, E, Answer>,
+    GuardT: Guard<E, GErr>,
+    E: 'static,
+    Event: EventKind,
+{
+    fn probe<'e>(
+        &self,
+        _: &PhantomData<Vertex>,
+        event: &'e Event::Of<'e>,
+        probe: &mut Probe<GErr>,
+    ) {
+        let outcome =
+            Single::<E>::with_matched::<Event, _>(event, |event| self.guard.check_each(event));
+        let transition = TransitionName::of::<Vertex, Event::Of<'e>, Vertex>();
+        let priority = OwnedActionLoop::<Vertex, Ctx, E, Answer>::priority(&self.action);
+        probe.offer_owned(priority, Kind::Specific, transition, outcome);
+    }
+
+    fn fire<'e>(
+        &mut self,
+        _: &mut PhantomData<Vertex>,
+        ctx: &mut Ctx,
+        event: FireEvent<'e, '_, Event>,
+        vertexes: &mut Vertexes,
+        position: &mut usize,
+    ) -> ProcessResultInner<(Answer, PhantomData<Vertex>), GErr> {
+        use ProcessResultInner::*;
+        if !reached(position) {
+            return EventTypeNotSatisfy;
+        }
+        let Some(event) = event.take::<E>() else {
+            return NoTransitions;
+        };
+        let Some(vertex) = vertexes.slot_mut().get_mut() else {
+            return NoTransitions;
+        };
+        let answer = self.action.trigger(vertex, ctx, event);
+
+        vertex.exit();
+        vertex.entry();
+        HandledAndProcessEnd((answer, PhantomData))
+    }
+}
+
+/// Transition from all vertices of `StateMachine` to the `Target` vertex.
+///
+/// `Target` vertex itself and vertices from `Except` list are not affected by this transition.
+pub struct ForallTransition<Ctx, Trig, Action, Guard, Target, Answer, GErr, Except> {
+    pub(crate) action: Action,
+    pub(crate) guard: Guard,
+    phantom: PhantomData<(Ctx, Trig, Target, Answer, GErr, Except)>,
+}
+
+impl<Ctx, Trig, Action: Clone, Guard: Clone, Target, Answer, GErr, Except> Clone
+    for ForallTransition<Ctx, Trig, Action, Guard, Target, Answer, GErr, Except>
+{
+    fn clone(&self) -> Self {
+        ForallTransition {
+            action: self.action.clone(),
+            guard: self.guard.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Ctx, Trig, ActionT, GuardT, Target, Answer, GErr, Except>
+    ForallTransition<Ctx, Trig, ActionT, GuardT, Target, Answer, GErr, Except>
+where
+    Trig: Trigger,
+    ActionT: for<'a> ForallAction<Ctx, Trig::Event<'a>, Target, Answer>,
+    GuardT: for<'a> Guard<Trig::Event<'a>, GErr>,
+    Except: TypeList,
+{
+    pub fn new(action: ActionT, guard: GuardT) -> Self {
+        ForallTransition {
+            action,
+            guard,
+            phantom: PhantomData,
+        }
     }
 }
 
@@ -324,9 +981,11 @@ impl<
         GErr,
         ActionT,
         GuardT,
-        TransEvent,
+        Trig,
+        Except,
         Idx1,
         Idx2,
+        ExceptIdx,
     >
     ITransition<
         PhantomData<Source>,
@@ -336,117 +995,393 @@ impl<
         Vertexes,
         Answer,
         GErr,
-        (TransEvent, Idx1, Idx2),
-    > for ForallTransition<ActionT, GuardT>
+        (Idx1, Idx2, ExceptIdx),
+    > for ForallTransition<Ctx, Trig, ActionT, GuardT, Target, Answer, GErr, Except>
 where
-    ActionT: Action<Source, Ctx, TransEvent, Target, Answer>,
-    GuardT: Guard<TransEvent, GErr>,
-    Vertexes: SelectorPointer<Source, Idx1> + SelectorPointer<Target, Idx2>,
+    Trig: Trigger,
+    ActionT: for<'a> ForallAction<Ctx, Trig::Event<'a>, Target, Answer>,
+    GuardT: for<'a> Guard<Trig::Event<'a>, GErr>,
+    Except: VertexIndices<Vertexes, ExceptIdx>,
+    ExceptIdx: ContainsIndex<Idx1>,
+    Idx1: SameIndex<Idx2>,
+    Vertexes: VertexSelector<Source, Idx1> + VertexSelector<Target, Idx2>,
+    <Vertexes as VertexSelector<Source, Idx1>>::Policy: ExitPolicy<Source>,
+    <Vertexes as VertexSelector<Target, Idx2>>::Policy:
+        for<'a> EntryPolicy<Target, Ctx, Trig::Event<'a>>,
     Source: ExitVertex + 'static,
     Target: EntryVertex + 'static,
-    Event: 'static,
-    TransEvent: 'static,
+    Event: EventKind,
 {
-    fn process(
+    fn probe<'e>(
+        &self,
+        _: &PhantomData<Source>,
+        event: &'e Event::Of<'e>,
+        probe: &mut Probe<GErr>,
+    ) {
+        if Idx1::SAME || ExceptIdx::CONTAINS {
+            return probe.skip();
+        }
+        let outcome = Trig::with_matched::<Event, _>(event, |event| self.guard.check_each(event));
+        let transition = TransitionName::of::<Source, Event::Of<'e>, Target>();
+        let priority = ForallAction::<Ctx, Trig::Event<'_>, Target, Answer>::priority(&self.action);
+        probe.offer(priority, Kind::Forall, transition, outcome);
+    }
+
+    fn fire<'e>(
         &mut self,
         _: &mut PhantomData<Source>,
         ctx: &mut Ctx,
-        event: &Event,
+        event: FireEvent<'e, '_, Event>,
         vertexes: &mut Vertexes,
+        position: &mut usize,
     ) -> ProcessResultInner<(Answer, PhantomData<Target>), GErr> {
         use ProcessResultInner::*;
-        if TypeId::of::<Event>() == TypeId::of::<TransEvent>() {
-            let event = unsafe { &*(event as *const Event as *const TransEvent) };
-            if TypeId::of::<Source>() == TypeId::of::<Target>() {
-                return ProcessResultInner::NoTransitions;
-            }
-
-            match self.guard.check(event) {
-                Ok(_) => {
-                    let source =
-                        unsafe { &mut *SelectorPointer::<Source, Idx1>::get_mut_ptr(vertexes) };
-                    let target =
-                        unsafe { &mut *SelectorPointer::<Target, Idx2>::get_mut_ptr(vertexes) };
-                    let answer = self.action.trigger(source, ctx, &event, target);
+        if !reached(position) {
+            return EventTypeNotSatisfy;
+        }
+        let ForallTransition { action, .. } = self;
+        event
+            .with_matched::<Trig, _>(|event| {
+                let target = VertexSelector::<Target, Idx2>::slot_mut(vertexes);
+                let Some(target) = target.enter(ctx, event) else {
+                    return NoTransitions;
+                };
+                let answer = action.trigger(ctx, event, target);
 
-                    source.exit();
+                VertexSelector::<Source, Idx1>::slot_mut(vertexes).leave();
+                if let Some(target) = VertexSelector::<Target, Idx2>::vertex_mut(vertexes) {
                     target.entry();
-                    HandledAndProcessEnd((answer, PhantomData))
                 }
-                Err(e) => GuardErr(e),
-            }
-        } else {
-            ProcessResultInner::EventTypeNotSatisfy
+                HandledAndProcessEnd((answer, PhantomData))
+            })
+            .unwrap_or(NoTransitions)
+    }
+}
+
+/// Internal transition that can be started from all vertices of `StateMachine` and does not leave
+/// the current vertex.
+///
+/// Vertices from `Except` list are not affected by this transition.
+pub struct ForallInternalTransition<Ctx, Trig, Action, Guard, Answer, GErr, Except> {
+    pub(crate) action: Action,
+    pub(crate) guard: Guard,
+    phantom: PhantomData<(Ctx, Trig, Answer, GErr, Except)>,
+}
+
+impl<Ctx, Trig, Action: Clone, Guard: Clone, Answer, GErr, Except> Clone
+    for ForallInternalTransition<Ctx, Trig, Action, Guard, Answer, GErr, Except>
+{
+    fn clone(&self) -> Self {
+        ForallInternalTransition {
+            action: self.action.clone(),
+            guard: self.guard.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Ctx, Trig, ActionT, GuardT, Answer, GErr, Except>
+    ForallInternalTransition<Ctx, Trig, ActionT, GuardT, Answer, GErr, Except>
+where
+    Trig: Trigger,
+    ActionT: for<'a> InternalAction<Ctx, Trig::Event<'a>, Answer>,
+    GuardT: for<'a> Guard<Trig::Event<'a>, GErr>,
+    Except: TypeList,
+{
+    pub fn new(action: ActionT, guard: GuardT) -> Self {
+        ForallInternalTransition {
+            action,
+            guard,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Source, Ctx, Event, Vertexes, Answer, GErr, ActionT, GuardT, Trig, Except, Idx, ExceptIdx>
+    ITransition<
+        PhantomData<Source>,
+        Ctx,
+        Event,
+        PhantomData<Source>,
+        Vertexes,
+        Answer,
+        GErr,
+        (Idx, ExceptIdx),
+    > for ForallInternalTransition<Ctx, Trig, ActionT, GuardT, Answer, GErr, Except>
+where
+    Trig: Trigger,
+    ActionT: for<'a> InternalAction<Ctx, Trig::Event<'a>, Answer>,
+    GuardT: for<'a> Guard<Trig::Event<'a>, GErr>,
+    Vertexes: VertexSelector<Source, Idx>,
+    Except: VertexIndices<Vertexes, ExceptIdx>,
+    ExceptIdx: ContainsIndex<Idx>,
+    Source: 'static,
+    Event: EventKind,
+{
+    fn probe<'e>(
+        &self,
+        _: &PhantomData<Source>,
+        event: &'e Event::Of<'e>,
+        probe: &mut Probe<GErr>,
+    ) {
+        if ExceptIdx::CONTAINS {
+            return probe.skip();
+        }
+        let outcome = Trig::with_matched::<Event, _>(event, |event| self.guard.check_each(event));
+        let transition = TransitionName::of::<Source, Event::Of<'e>, Source>();
+        let priority = InternalAction::<Ctx, Trig::Event<'_>, Answer>::priority(&self.action);
+        probe.offer(priority, Kind::Forall, transition, outcome);
+    }
+
+    fn fire<'e>(
+        &mut self,
+        _: &mut PhantomData<Source>,
+        ctx: &mut Ctx,
+        event: FireEvent<'e, '_, Event>,
+        _: &mut Vertexes,
+        position: &mut usize,
+    ) -> ProcessResultInner<(Answer, PhantomData<Source>), GErr> {
+        use ProcessResultInner::*;
+        if !reached(position) {
+            return EventTypeNotSatisfy;
+        }
+        let ForallInternalTransition { action, .. } = self;
+        event
+            .with_matched::<Trig, _>(|event| {
+                HandledAndProcessEnd((action.trigger(ctx, event), PhantomData))
+            })
+            .unwrap_or(NoTransitions)
+    }
+}
+
+/// Marker of an event that is ignored by the `Vertex` explicitly. It is never fired, but it turns
+/// `ProcessResult::NoTransitions` and `ProcessResult::GuardErr` into `ProcessResult::Ignored`.
+pub struct IgnoreTransition<Vertex, Trig> {
+    phantom: PhantomData<(Vertex, Trig)>,
+}
+
+impl<Vertex, Trig> Clone for IgnoreTransition<Vertex, Trig> {
+    fn clone(&self) -> Self {
+        IgnoreTransition::new()
+    }
+}
+
+impl<Vertex, Trig> IgnoreTransition<Vertex, Trig> {
+    pub fn new() -> Self {
+        IgnoreTransition {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Vertex, Trig> Default for IgnoreTransition<Vertex, Trig> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Vertex, Ctx, Trig, Event, Vertexes, Answer, GErr>
+    ITransition<PhantomData<Vertex>, Ctx, Event, PhantomData<Vertex>, Vertexes, Answer, GErr, ()>
+    for IgnoreTransition<Vertex, Trig>
+where
+    Trig: Trigger,
+    Event: EventKind,
+{
+    fn probe<'e>(
+        &self,
+        _: &PhantomData<Vertex>,
+        event: &'e Event::Of<'e>,
+        probe: &mut Probe<GErr>,
+    ) {
+        match Trig::with_matched::<Event, _>(event, |_| ()) {
+            Some(()) => probe.ignore(),
+            None => probe.skip(),
+        }
+    }
+
+    fn fire<'e>(
+        &mut self,
+        _: &mut PhantomData<Vertex>,
+        _: &mut Ctx,
+        _: FireEvent<'e, '_, Event>,
+        _: &mut Vertexes,
+        position: &mut usize,
+    ) -> ProcessResultInner<(Answer, PhantomData<Vertex>), GErr> {
+        reached(position);
+        ProcessResultInner::EventTypeNotSatisfy
+    }
+}
+
+/// Marker of an event that is deferred by the `Vertex` explicitly. It is never fired, but it turns
+/// `ProcessResult::NoTransitions` and `ProcessResult::GuardErr` into `ProcessResult::Deferred`.
+/// If the `Vertex` also ignores the event, `ProcessResult::Ignored` is returned.
+pub struct DeferTransition<Vertex, Trig> {
+    phantom: PhantomData<(Vertex, Trig)>,
+}
+
+impl<Vertex, Trig> Clone for DeferTransition<Vertex, Trig> {
+    fn clone(&self) -> Self {
+        DeferTransition::new()
+    }
+}
+
+impl<Vertex, Trig> DeferTransition<Vertex, Trig> {
+    pub fn new() -> Self {
+        DeferTransition {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Vertex, Trig> Default for DeferTransition<Vertex, Trig> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Vertex, Ctx, Trig, Event, Vertexes, Answer, GErr>
+    ITransition<PhantomData<Vertex>, Ctx, Event, PhantomData<Vertex>, Vertexes, Answer, GErr, ()>
+    for DeferTransition<Vertex, Trig>
+where
+    Trig: Trigger,
+    Event: EventKind,
+{
+    fn probe<'e>(
+        &self,
+        _: &PhantomData<Vertex>,
+        event: &'e Event::Of<'e>,
+        probe: &mut Probe<GErr>,
+    ) {
+        match Trig::with_matched::<Event, _>(event, |_| ()) {
+            Some(()) => probe.defer(),
+            None => probe.skip(),
+        }
+    }
+
+    fn fire<'e>(
+        &mut self,
+        _: &mut PhantomData<Vertex>,
+        _: &mut Ctx,
+        _: FireEvent<'e, '_, Event>,
+        _: &mut Vertexes,
+        position: &mut usize,
+    ) -> ProcessResultInner<(Answer, PhantomData<Vertex>), GErr> {
+        reached(position);
+        ProcessResultInner::EventTypeNotSatisfy
+    }
+}
+
+/// Transition that is started from the `Source` vertex when neither typed nor forall transitions
+/// accept the event.
+///
+/// It is stored in the end of forall transitions, but it is probed only when no other transition
+/// accepts the event.
+pub struct CatchAllTransition<Source, Trans> {
+    pub(crate) transition: Trans,
+    phantom: PhantomData<Source>,
+}
+
+impl<Source, Trans: Clone> Clone for CatchAllTransition<Source, Trans> {
+    fn clone(&self) -> Self {
+        CatchAllTransition {
+            transition: self.transition.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Source, Trans> CatchAllTransition<Source, Trans> {
+    pub fn new(transition: Trans) -> Self {
+        CatchAllTransition {
+            transition,
+            phantom: PhantomData,
         }
     }
 }
 
 impl<
-        ActionT,
-        GuardT,
+        Current,
         Source,
+        Trans,
         Ctx,
-        Event,
+        Event: EventKind,
         Target,
         Vertexes,
         Answer,
         GErr,
-        Rest,
         Other,
-        OtherRest,
-        TargetUnit,
-        Idx,
+        CurrentIdx,
+        SourceIdx,
     >
     ITransition<
-        PhantomData<Source>,
+        PhantomData<Current>,
         Ctx,
         Event,
         Target,
         Vertexes,
         Answer,
         GErr,
-        (Other, OtherRest, TargetUnit, Idx, ()),
-    > for HCons<(ForallTransition<ActionT, GuardT>, PhantomData<ActionT>), Rest>
+        (Other, CurrentIdx, SourceIdx),
+    > for CatchAllTransition<Source, Trans>
 where
-    ForallTransition<ActionT, GuardT>: ITransition<
-        PhantomData<Source>,
-        Ctx,
-        Event,
-        PhantomData<TargetUnit>,
-        Vertexes,
-        Answer,
-        GErr,
-        Other,
-    >,
-    Rest: ITransition<PhantomData<Source>, Ctx, Event, Target, Vertexes, Answer, GErr, OtherRest>,
-    Target: CoprodInjector<PhantomData<TargetUnit>, Idx>,
+    Trans: ITransition<PhantomData<Source>, Ctx, Event, Target, Vertexes, Answer, GErr, Other>,
+    Vertexes: VertexSelector<Current, CurrentIdx> + VertexSelector<Source, SourceIdx>,
+    CurrentIdx: SameIndex<SourceIdx>,
 {
-    fn process(
+    fn probe<'e>(
+        &self,
+        _: &PhantomData<Current>,
+        event: &'e Event::Of<'e>,
+        probe: &mut Probe<GErr>,
+    ) {
+        if !CurrentIdx::SAME {
+            return probe.skip();
+        }
+        probe.catch_all_scope(|probe| self.transition.probe(&PhantomData, event, probe));
+    }
+
+    fn fire<'e>(
         &mut self,
-        source: &mut PhantomData<Source>,
+        _: &mut PhantomData<Current>,
         ctx: &mut Ctx,
-        event: &Event,
+        event: FireEvent<'e, '_, Event>,
         vertexes: &mut Vertexes,
+        position: &mut usize,
     ) -> ProcessResultInner<(Answer, Target), GErr> {
-        match self.head.0.process(source, ctx, event, vertexes) {
-            ProcessResultInner::NoTransitions => self.tail.process(source, ctx, event, vertexes),
-            ok => ok.map(|(a, t)| (a, Target::inject(t))),
-        }
+        self.transition
+            .fire(&mut PhantomData, ctx, event, vertexes, position)
     }
 }
 
-pub trait ProcessByForallTransitions<Transitions, Ctx, Event, Vertices, Answer, Target, GErr, Other>
+pub trait ProcessByForallTransitions<
+    Transitions,
+    Ctx,
+    Event: EventKind,
+    Vertices,
+    Answer,
+    Target,
+    GErr,
+    Other,
+>
 {
-    fn process_by(
+    fn probe_by<'e>(
+        &self,
+        transitions: &Transitions,
+        event: &'e Event::Of<'e>,
+        probe: &mut Probe<GErr>,
+    );
+
+    fn fire_by<'e>(
         &mut self,
         transitions: &mut Transitions,
         ctx: &mut Ctx,
-        event: &Event,
+        event: FireEvent<'e, '_, Event>,
         vertexes: &mut Vertices,
+        position: &mut usize,
     ) -> ProcessResultInner<(Answer, Target), GErr>;
 }
 
-impl<Trans, TransRest, Ctx, Event, Vertices, Answer, Target, GErr>
+impl<Trans, TransRest, Ctx, Event: EventKind, Vertices, Answer, Target, GErr>
     ProcessByForallTransitions<
         HCons<Trans, TransRest>,
         Ctx,
@@ -458,12 +1393,16 @@ impl<Trans, TransRest, Ctx, Event, Vertices, Answer, Target, GErr>
         (),
     > for Coproduct<PhantomData<TerminationPseudoState>, CNil>
 {
-    fn process_by(
+    fn probe_by<'e>(&self, _: &HCons<Trans, TransRest>, _: &'e Event::Of<'e>, _: &mut Probe<GErr>) {
+    }
+
+    fn fire_by<'e>(
         &mut self,
         _: &mut HCons<Trans, TransRest>,
         _: &mut Ctx,
-        _: &Event,
+        _: FireEvent<'e, '_, Event>,
         _: &mut Vertices,
+        _: &mut usize,
     ) -> ProcessResultInner<(Answer, Target), GErr> {
         ProcessResultInner::NoTransitions
     }
@@ -473,7 +1412,7 @@ impl<
         Trans,
         TransRest,
         Ctx,
-        Event,
+        Event: EventKind,
         Vertices,
         Answer,
         Target,
@@ -507,30 +1446,46 @@ where
         OtherRest,
     >,
 {
-    fn process_by(
+    fn probe_by<'e>(
+        &self,
+        transitions: &HCons<Trans, TransRest>,
+        event: &'e Event::Of<'e>,
+        probe: &mut Probe<GErr>,
+    ) {
+        match self {
+            Coproduct::Inl(l) => transitions.probe(l, event, probe),
+            Coproduct::Inr(r) => r.probe_by(transitions, event, probe),
+        }
+    }
+
+    fn fire_by<'e>(
         &mut self,
         transitions: &mut HCons<Trans, TransRest>,
         ctx: &mut Ctx,
-        event: &Event,
+        event: FireEvent<'e, '_, Event>,
         vertexes: &mut Vertices,
+        position: &mut usize,
     ) -> ProcessResultInner<(Answer, Target), GErr> {
         match self {
-            Coproduct::Inl(l) => transitions.process(l, ctx, event, vertexes),
-            Coproduct::Inr(r) => r.process_by(transitions, ctx, event, vertexes),
+            Coproduct::Inl(l) => transitions.fire(l, ctx, event, vertexes, position),
+            Coproduct::Inr(r) => r.fire_by(transitions, ctx, event, vertexes, position),
         }
     }
 }
 
-impl<Ctx, Event, Vertices, Answer, Target, GErr, CurSource, Rest>
+impl<Ctx, Event: EventKind, Vertices, Answer, Target, GErr, CurSource, Rest>
     ProcessByForallTransitions<HNil, Ctx, Event, Vertices, Answer, Target, GErr, ()>
     for Coproduct<PhantomData<CurSource>, Rest>
 {
-    fn process_by(
+    fn probe_by<'e>(&self, _: &HNil, _: &'e Event::Of<'e>, _: &mut Probe<GErr>) {}
+
+    fn fire_by<'e>(
         &mut self,
         _: &mut HNil,
         _: &mut Ctx,
-        _: &Event,
+        _: FireEvent<'e, '_, Event>,
         _: &mut Vertices,
+        _: &mut usize,
     ) -> ProcessResultInner<(Answer, Target), GErr> {
         ProcessResultInner::NoTransitions
     }
@@ -0,0 +1,555 @@
+//! Triggers of transitions.
+//!
+//! Trigger - a type that decides which events start a transition, and how the matched event is
+//! presented to `Guard` and `Action`.
+//!
+//! `Trigger` implemented for:
+//! - `Single<E>` - an event of type `E`. `Guard` and `Action` receive `&E`. This trigger is used by
+//!   `StateMachine::add_transition`, `StateMachine::add_loop` and forall transitions.
+//! - `AnyOf<Hlist![A, B, ...]>` - an event of any type from the list. `Guard` and `Action`
+//!   receive `&Coprod!(&A, &B, ...)` with the event that matched. In `state_machine!` it is written
+//!   as `Source + (A | B) => Target`.
+//! - `FamilyOf<dyn Trait>` - an event of any type that is a member of the `Family` of `dyn Trait`.
+//!   `Guard` and `Action` receive `&dyn Trait`. Members are listed once with `umlsm::family!`, so
+//!   a new member does not require changes in the `StateMachine` definition. In `state_machine!` it
+//!   is written as `Source + dyn Trait => Target`.
+//! - `Borrowed<K>` - an event of `EventKind` `K`, that may borrow data, such as `NewMessage<'a>`.
+//!   `Guard` and `Action` receive `&NewMessage<'a>`. In `state_machine!` it is written as
+//!   `Source + NewMessage<'_> => Target`, and the event is processed by
+//!   `StateMachine::process_borrowed`, or by `StateMachine::process` if it borrows only `'static`
+//!   data.
+//! - `Wildcard` - an event of any type. `Guard` and `Action` receive `&Unhandled` with the event as
+//!   `&dyn Any` (if it does not borrow data) and its type name. It is used by catch-all transitions, which are written in
+//!   `state_machine!` as `Source + _ => Target` or `Source + _ | handler`.
+//!
+//! Example:
+//! ```
+//! use umlsm::*;
+//! use umlsm::reexport::frunk::{Coprod, Coproduct};
+//! use umlsm::vertex::{EntryVertex, ExitVertex, InitialPseudoState};
+//!
+//! struct Locked;
+//! impl EntryVertex for Locked {}
+//! impl ExitVertex for Locked {}
+//! struct Unlocked;
+//! impl EntryVertex for Unlocked {}
+//! impl ExitVertex for Unlocked {}
+//!
+//! struct Push;
+//! struct Kick;
+//!
+//! fn unlock(_: &mut Locked, by: &mut &str, event: &Coprod!(&Push, &Kick), _: &mut Unlocked) {
+//!     *by = match event {
+//!         Coproduct::Inl(_) => "push",
+//!         Coproduct::Inr(_) => "kick",
+//!     };
+//! }
+//!
+//! let mut sm = umlsm::state_machine!(
+//!     state = "", err = (),
+//!     [Locked, Unlocked],
+//!
+//!     InitialPseudoState + ()                     => Locked,
+//!     Locked             + (Push | Kick) | unlock => Unlocked;
+//! );
+//! sm.process(&());
+//! sm.process(&Kick);
+//! assert!(sm.is::<Unlocked>());
+//! assert_eq!(*sm.state(), "kick");
+//! ```
+
+use crate::action::{Action, ActionLoop, ForallAction, InternalAction};
+use crate::Guard;
+use frunk::coproduct::CNil;
+use frunk::{Coproduct, HCons, HNil};
+use std::any::Any;
+use std::marker::PhantomData;
+
+/// An interface for transition triggers.
+///
+/// See module-level documentation for more information.
+pub trait Trigger {
+    /// The view of matched event that `Guard` and `Action` receive.
+    type Event<'a>: ?Sized;
+
+    /// Call `f` with the view of `event` if the trigger accepts `event`.
+    fn with_matched<'a, K: EventKind, R>(
+        event: &'a K::Of<'a>,
+        f: impl FnOnce(&Self::Event<'a>) -> R,
+    ) -> Option<R>;
+
+    /// Add type names of the events that the trigger accepts to `names`.
+    fn event_names(names: &mut Vec<&'static str>) {
+        names.push(std::any::type_name::<Self>());
+    }
+}
+
+/// A `'static` marker of an event type. Transitions are matched by kinds of events, so the events
+/// themselves may borrow data.
+///
+/// - `Static<T>` is the kind of a `'static` event type `T`.
+/// - A type with a lifetime, such as `NewMessage<'a>`, gets its kind by `umlsm::borrowed_event!`.
+pub trait EventKind: 'static {
+    /// The event type with the specified lifetime.
+    type Of<'a>;
+
+    /// Return `event` as `&dyn Any` if it does not borrow data.
+    fn as_any<'a>(event: &'a Self::Of<'a>) -> Option<&'a dyn Any> {
+        let _ = event;
+        None
+    }
+
+    /// Return the event from `Option` in `slot`, where `StateMachine::process_owned` keeps it.
+    fn from_owned(slot: &dyn Any) -> Option<&Self::Of<'_>> {
+        let _ = slot;
+        None
+    }
+
+    /// Return `event` of kind `K` as an event of this kind if it is the `'static` instance of
+    /// this kind processed by `StateMachine::process`, such as `NewMessage<'static>` of kind
+    /// `Static<NewMessage<'static>>`.
+    fn from_static<'a, K: EventKind>(event: &'a K::Of<'a>) -> Option<&'a Self::Of<'a>> {
+        let _ = event;
+        None
+    }
+}
+
+/// The kind of a `'static` event type `T`.
+pub struct Static<T>(PhantomData<T>);
+
+impl<T: 'static> EventKind for Static<T> {
+    type Of<'a> = T;
+
+    fn as_any(event: &T) -> Option<&dyn Any> {
+        Some(event)
+    }
+
+    fn from_owned(slot: &dyn Any) -> Option<&T> {
+        slot.downcast_ref::<Option<T>>()?.as_ref()
+    }
+}
+
+/// An event type that borrows data. Use `umlsm::borrowed_event!` to implement it.
+pub trait BorrowedEvent<'a> {
+    type Kind: EventKind<Of<'a> = Self>;
+}
+
+/// Implement `EventKind` and `BorrowedEvent` for a type with one lifetime parameter. The type must
+/// be covariant in it.
+///
+/// Example:
+/// ```
+/// use umlsm::*;
+/// use umlsm::vertex::{EntryVertex, ExitVertex, InitialPseudoState};
+///
+/// struct Idle;
+/// impl EntryVertex for Idle {}
+/// impl ExitVertex for Idle {}
+///
+/// struct NewMessage<'a>(&'a str);
+/// umlsm::borrowed_event!(NewMessage<'a>);
+///
+/// fn start(_: &mut InitialPseudoState, _: &mut (), _: &(), _: &mut Idle) -> String {
+///     String::new()
+/// }
+///
+/// fn echo(_: &mut Idle, _: &mut (), message: &NewMessage) -> String {
+///     message.0.to_uppercase()
+/// }
+///
+/// let mut sm = umlsm::state_machine!(
+///     state = (), err = (),
+///     [Idle],
+///
+///     InitialPseudoState + () | start => Idle;
+///
+///     loop:
+///     Idle + NewMessage<'_> | echo;
+/// );
+/// sm.process(&());
+///
+/// let buffer = String::from("hello");
+/// assert_eq!(sm.process_borrowed(&NewMessage(&buffer)).unwrap(), "HELLO");
+/// ```
+#[macro_export]
+macro_rules! borrowed_event {
+    ($($name:ident)::+ <$lt:lifetime>) => {
+        impl $crate::trigger::EventKind for $($name)::+<'static> {
+            type Of<$lt> = $($name)::+<$lt>;
+
+            fn from_static<'e, K: $crate::trigger::EventKind>(
+                event: &'e K::Of<'e>,
+            ) -> Option<&'e $($name)::+<'e>> {
+                let event: &'e $($name)::+<'static> =
+                    $crate::trigger::cast::<K, $crate::trigger::Static<$($name)::+<'static>>>(event)?;
+                Some(event)
+            }
+        }
+
+        impl<$lt> $crate::trigger::BorrowedEvent<$lt> for $($name)::+<$lt> {
+            type Kind = $($name)::+<'static>;
+        }
+    };
+}
+
+/// Return `event` of kind `K` as an event of kind `J` if they are the same kind.
+///
+/// The identity function for `K` is a `'static` value, so it can be downcast to the identity
+/// function for `J` without `unsafe`. It succeeds only if `K` and `J` are the same type. The
+/// downcast compares `TypeId`s at runtime, and every trigger probed for an event calls it.
+pub fn cast<'a, K: EventKind, J: EventKind>(event: &'a K::Of<'a>) -> Option<&'a J::Of<'a>> {
+    fn identity<'a, K: EventKind>(event: &'a K::Of<'a>, out: &mut Option<&'a K::Of<'a>>) {
+        *out = Some(event);
+    }
+
+    let identity: for<'b> fn(&'b K::Of<'b>, &mut Option<&'b K::Of<'b>>) = identity::<K>;
+    let cast = (&identity as &dyn Any)
+        .downcast_ref::<for<'b> fn(&'b K::Of<'b>, &mut Option<&'b J::Of<'b>>)>()?;
+    let mut out = None;
+    cast(event, &mut out);
+    out
+}
+
+/// Trigger that accepts an event of type `E`.
+pub struct Single<E>(PhantomData<E>);
+
+impl<T: 'static> Trigger for Single<T> {
+    type Event<'a> = T;
+
+    fn with_matched<'a, K: EventKind, R>(
+        event: &'a K::Of<'a>,
+        f: impl FnOnce(&Self::Event<'a>) -> R,
+    ) -> Option<R> {
+        cast::<K, Static<T>>(event).map(f)
+    }
+
+    fn event_names(names: &mut Vec<&'static str>) {
+        names.push(std::any::type_name::<T>());
+    }
+}
+
+/// Trigger that accepts an event of kind `K`, which may borrow data. In `state_machine!` it is
+/// written as `Source + NewMessage<'_> => Target`.
+///
+/// An event that borrows only `'static` data, such as `NewMessage("hello")`, may also be processed
+/// by `StateMachine::process`, and it is accepted the same way.
+pub struct Borrowed<K>(PhantomData<K>);
+
+impl<J: EventKind> Trigger for Borrowed<J> {
+    type Event<'a> = J::Of<'a>;
+
+    fn with_matched<'a, K: EventKind, R>(
+        event: &'a K::Of<'a>,
+        f: impl FnOnce(&Self::Event<'a>) -> R,
+    ) -> Option<R> {
+        cast::<K, J>(event)
+            .or_else(|| J::from_static::<K>(event))
+            .map(f)
+    }
+
+    fn event_names(names: &mut Vec<&'static str>) {
+        names.push(std::any::type_name::<J::Of<'static>>());
+    }
+}
+
+/// Trigger that accepts an event of any type from the `EventSet`.
+pub struct AnyOf<L>(PhantomData<L>);
+
+impl<L: EventSet> Trigger for AnyOf<L> {
+    type Event<'a> = L::Refs<'a>;
+
+    fn with_matched<'a, K: EventKind, R>(
+        event: &'a K::Of<'a>,
+        f: impl FnOnce(&Self::Event<'a>) -> R,
+    ) -> Option<R> {
+        L::matches::<K>(event).map(|refs| f(&refs))
+    }
+
+    fn event_names(names: &mut Vec<&'static str>) {
+        L::names(names);
+    }
+}
+
+/// Trigger that accepts an event of any type from the `Family` of `F`.
+pub struct FamilyOf<F: ?Sized>(PhantomData<F>);
+
+impl<F: Family + ?Sized> Trigger for FamilyOf<F> {
+    type Event<'a> = F;
+
+    fn with_matched<'a, K: EventKind, R>(
+        event: &'a K::Of<'a>,
+        f: impl FnOnce(&Self::Event<'a>) -> R,
+    ) -> Option<R> {
+        K::as_any(event).and_then(F::cast).map(f)
+    }
+
+    fn event_names(names: &mut Vec<&'static str>) {
+        names.push(std::any::type_name::<F>());
+    }
+}
+
+/// A group of event types that share one trait, such as `dyn Cancel`. A family of vertex types is
+/// used by `StateMachine::with_current`.
+///
+/// Rust cannot check in generic code whether a type implements a trait, so the members of the
+/// family must be listed explicitly. Use `umlsm::family!` to implement it.
+pub trait Family: 'static {
+    /// The trait object with a shorter lifetime, such as `dyn Cancel + 'a`.
+    type Object<'a>: ?Sized;
+
+    /// Return `event` as a reference to the family if its type is a member of the family.
+    fn cast(event: &dyn Any) -> Option<&Self>;
+
+    /// Return `event` as a mutable reference to the family if its type is a member of the family.
+    fn cast_mut(event: &mut dyn Any) -> Option<&mut Self>;
+
+    /// Shorten the lifetime of the trait object to the lifetime of the reference.
+    fn shorten(event: &Self) -> &Self::Object<'_>;
+
+    /// Shorten the lifetime of the trait object to the lifetime of the mutable reference.
+    fn shorten_mut(event: &mut Self) -> &mut Self::Object<'_>;
+}
+
+/// Adapter for actions which take the event as `&dyn Trait`.
+///
+/// A function `fn(.., &dyn Cancel, ..)` takes `&'a (dyn Cancel + 'a)`, so it does not implement
+/// `Action` for the event type `dyn Cancel + 'static`. `FamilyAction` implements `Action`,
+/// `ActionLoop`, `ForallAction` and `InternalAction` for such functions. `state_machine!` wraps
+/// actions of `dyn Trait` transitions automatically.
+#[derive(Clone)]
+pub struct FamilyAction<F>(F);
+
+impl<F> FamilyAction<F> {
+    pub fn new(action: F) -> Self {
+        FamilyAction(action)
+    }
+}
+
+impl<Source, Ctx, Fam, Target, Answer, F> Action<Source, Ctx, Fam, Target, Answer>
+    for FamilyAction<F>
+where
+    Fam: Family + ?Sized,
+    F: for<'a> Fn(&mut Source, &mut Ctx, &'a Fam::Object<'a>, &mut Target) -> Answer,
+{
+    fn trigger(
+        &self,
+        source: &mut Source,
+        ctx: &mut Ctx,
+        event: &Fam,
+        target: &mut Target,
+    ) -> Answer {
+        (self.0)(source, ctx, Fam::shorten(event), target)
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<F>())
+    }
+}
+
+impl<Source, Ctx, Fam, Answer, F> ActionLoop<Source, Ctx, Fam, Answer> for FamilyAction<F>
+where
+    Fam: Family + ?Sized,
+    F: for<'a> Fn(&mut Source, &mut Ctx, &'a Fam::Object<'a>) -> Answer,
+{
+    fn trigger(&self, source: &mut Source, ctx: &mut Ctx, event: &Fam) -> Answer {
+        (self.0)(source, ctx, Fam::shorten(event))
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<F>())
+    }
+}
+
+impl<Ctx, Fam, Target, Answer, F> ForallAction<Ctx, Fam, Target, Answer> for FamilyAction<F>
+where
+    Fam: Family + ?Sized,
+    F: for<'a> Fn(&mut Ctx, &'a Fam::Object<'a>, &mut Target) -> Answer,
+{
+    fn trigger(&self, ctx: &mut Ctx, event: &Fam, target: &mut Target) -> Answer {
+        (self.0)(ctx, Fam::shorten(event), target)
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<F>())
+    }
+}
+
+impl<Ctx, Fam, Answer, F> InternalAction<Ctx, Fam, Answer> for FamilyAction<F>
+where
+    Fam: Family + ?Sized,
+    F: for<'a> Fn(&mut Ctx, &'a Fam::Object<'a>) -> Answer,
+{
+    fn trigger(&self, ctx: &mut Ctx, event: &Fam) -> Answer {
+        (self.0)(ctx, Fam::shorten(event))
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<F>())
+    }
+}
+
+/// Adapter for guards which take the event as `&dyn Trait`. See `FamilyAction`.
+#[derive(Clone)]
+pub struct FamilyGuard<F>(F);
+
+impl<F> FamilyGuard<F> {
+    pub fn new(guard: F) -> Self {
+        FamilyGuard(guard)
+    }
+}
+
+impl<Fam, Err, F> Guard<Fam, Err> for FamilyGuard<F>
+where
+    Fam: Family + ?Sized,
+    F: for<'a> Fn(&'a Fam::Object<'a>) -> Result<(), Err>,
+{
+    fn check(&self, input: &Fam) -> Result<(), Err> {
+        (self.0)(Fam::shorten(input))
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<F>())
+    }
+}
+
+/// Implement `Family` for a trait object.
+///
+/// Example:
+/// ```
+/// use umlsm::trigger::Family;
+///
+/// trait Cancel {
+///     fn reason(&self) -> &'static str;
+/// }
+///
+/// struct Abort;
+/// impl Cancel for Abort {
+///     fn reason(&self) -> &'static str { "abort" }
+/// }
+///
+/// struct Timeout;
+/// impl Cancel for Timeout {
+///     fn reason(&self) -> &'static str { "timeout" }
+/// }
+///
+/// umlsm::family!(dyn Cancel: Abort, Timeout);
+///
+/// assert_eq!(<dyn Cancel>::cast(&Timeout).unwrap().reason(), "timeout");
+/// assert!(<dyn Cancel>::cast(&()).is_none());
+/// ```
+#[macro_export]
+macro_rules! family {
+    (dyn $family:path: $($member:ty),* $(,)?) => {
+        impl $crate::trigger::Family for dyn $family {
+            type Object<'a> = dyn $family + 'a;
+
+            fn shorten(event: &Self) -> &Self::Object<'_> {
+                event
+            }
+
+            fn shorten_mut(event: &mut Self) -> &mut Self::Object<'_> {
+                event
+            }
+
+            fn cast(event: &dyn std::any::Any) -> Option<&Self> {
+                $(
+                    if let Some(event) = event.downcast_ref::<$member>() {
+                        return Some(event);
+                    }
+                )*
+                None
+            }
+
+            fn cast_mut(event: &mut dyn std::any::Any) -> Option<&mut Self> {
+                $(
+                    if event.is::<$member>() {
+                        return event.downcast_mut::<$member>().map(|event| event as &mut Self);
+                    }
+                )*
+                None
+            }
+        }
+    };
+}
+
+/// Trigger that accepts an event of any type.
+pub struct Wildcard;
+
+impl Trigger for Wildcard {
+    type Event<'a> = Unhandled<'a>;
+
+    fn with_matched<'a, K: EventKind, R>(
+        event: &'a K::Of<'a>,
+        f: impl FnOnce(&Self::Event<'a>) -> R,
+    ) -> Option<R> {
+        Some(f(&Unhandled {
+            event: K::as_any(event),
+            type_name: std::any::type_name::<K::Of<'a>>(),
+        }))
+    }
+
+    fn event_names(names: &mut Vec<&'static str>) {
+        names.push("_");
+    }
+}
+
+/// An event that is accepted by the `Wildcard` trigger.
+pub struct Unhandled<'a> {
+    event: Option<&'a dyn Any>,
+    type_name: &'static str,
+}
+
+impl<'a> Unhandled<'a> {
+    /// The event itself, or `None` if the event borrows data.
+    pub fn event(&self) -> Option<&'a dyn Any> {
+        self.event
+    }
+
+    /// The type name of the event, as returned by `std::any::type_name`.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Return a reference to the event if it has type `T`.
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&'a T> {
+        self.event?.downcast_ref()
+    }
+}
+
+/// A type-level list of event types, such as `Hlist![A, B]`.
+pub trait EventSet {
+    /// Coproduct of references to the events from the list, such as `Coprod!(&'a A, &'a B)`.
+    type Refs<'a>;
+
+    /// Return a reference to `event` if its type is in the list.
+    fn matches<'a, K: EventKind>(event: &'a K::Of<'a>) -> Option<Self::Refs<'a>>;
+
+    /// Add type names of the events from the list to `names`.
+    fn names(names: &mut Vec<&'static str>);
+}
+
+impl EventSet for HNil {
+    type Refs<'a> = CNil;
+
+    fn matches<'a, K: EventKind>(_: &'a K::Of<'a>) -> Option<Self::Refs<'a>> {
+        None
+    }
+
+    fn names(_: &mut Vec<&'static str>) {}
+}
+
+impl<H: 'static, Rest: EventSet> EventSet for HCons<H, Rest> {
+    type Refs<'a> = Coproduct<&'a H, Rest::Refs<'a>>;
+
+    fn matches<'a, K: EventKind>(event: &'a K::Of<'a>) -> Option<Self::Refs<'a>> {
+        match cast::<K, Static<H>>(event) {
+            Some(head) => Some(Coproduct::Inl(head)),
+            None => Rest::matches::<K>(event).map(Coproduct::Inr),
+        }
+    }
+
+    fn names(names: &mut Vec<&'static str>) {
+        names.push(std::any::type_name::<H>());
+        Rest::names(names);
+    }
+}
@@ -1,6 +1,9 @@
+use crate::vertex::{EntryVertex, InitialPseudoState, StateMachineVertex, VertexSlot};
+use crate::StateMachine;
 use frunk::coproduct::CNil;
 use frunk::indices::{Here, There};
-use frunk::{Coproduct, HCons, HNil};
+use frunk::{Coproduct, HCons, HNil, Hlist};
+use std::any::Any;
 use std::marker::PhantomData;
 
 pub trait CoprodWithoutPhantomData {
@@ -34,58 +37,485 @@ where
     type CoprodWithRef = Coproduct<&'a L, R::CoprodWithRef>;
 }
 
+/// Returns the type name of the current vertex.
+pub trait VertexName {
+    fn vertex_name(&self) -> &'static str;
+}
+
+impl VertexName for CNil {
+    fn vertex_name(&self) -> &'static str {
+        match *self {}
+    }
+}
+
+impl<V, Rest: VertexName> VertexName for Coproduct<PhantomData<V>, Rest> {
+    fn vertex_name(&self) -> &'static str {
+        match self {
+            Coproduct::Inl(_) => std::any::type_name::<V>(),
+            Coproduct::Inr(rest) => rest.vertex_name(),
+        }
+    }
+}
+
+/// Checks that the current vertex is final, i.e. `Final<Outcome>` or `TerminationPseudoState`.
+/// Every vertex except `InitialPseudoState` can be entered, so it is checked by `EntryVertex::FINAL`.
+pub trait IsTerminated {
+    fn is_terminated(&self) -> bool;
+}
+
+impl IsTerminated for CNil {
+    fn is_terminated(&self) -> bool {
+        match *self {}
+    }
+}
+
+impl<Rest: IsTerminated> IsTerminated for Coproduct<PhantomData<InitialPseudoState>, Rest> {
+    fn is_terminated(&self) -> bool {
+        match self {
+            Coproduct::Inl(_) => false,
+            Coproduct::Inr(rest) => rest.is_terminated(),
+        }
+    }
+}
+
+impl<V: EntryVertex, Rest: IsTerminated> IsTerminated for Coproduct<PhantomData<V>, Rest> {
+    fn is_terminated(&self) -> bool {
+        match self {
+            Coproduct::Inl(_) => V::FINAL,
+            Coproduct::Inr(rest) => rest.is_terminated(),
+        }
+    }
+}
+
+/// Returns a `Coproduct` of references to the current vertex, or `None` if its slot is empty.
 pub trait GetRefsFromCoprod<'a, C> {
     type Out: 'a;
-    fn get_refs(&'a self, c: &C) -> Self::Out;
+    fn get_refs(&'a self, c: &C) -> Option<Self::Out>;
 }
 
 impl<'a> GetRefsFromCoprod<'a, CNil> for HNil {
     type Out = CNil;
 
-    fn get_refs(&'a self, c: &CNil) -> Self::Out {
+    fn get_refs(&'a self, c: &CNil) -> Option<Self::Out> {
         match *c {}
     }
 }
 
-impl<'a, T, CRest, Rest> GetRefsFromCoprod<'a, Coproduct<PhantomData<T>, CRest>> for HCons<T, Rest>
+impl<'a, T, Policy, CRest, Rest> GetRefsFromCoprod<'a, Coproduct<PhantomData<T>, CRest>>
+    for HCons<VertexSlot<T, Policy>, Rest>
 where
     T: 'a,
     Rest: GetRefsFromCoprod<'a, CRest>,
 {
     type Out = Coproduct<&'a T, Rest::Out>;
 
-    fn get_refs(&'a self, c: &Coproduct<PhantomData<T>, CRest>) -> Self::Out {
+    fn get_refs(&'a self, c: &Coproduct<PhantomData<T>, CRest>) -> Option<Self::Out> {
         match c {
-            Coproduct::Inl(_) => Coproduct::Inl(&self.head),
-            Coproduct::Inr(r) => Coproduct::Inr(self.tail.get_refs(r)),
+            Coproduct::Inl(_) => self.head.get().map(Coproduct::Inl),
+            Coproduct::Inr(r) => self.tail.get_refs(r).map(Coproduct::Inr),
         }
     }
 }
 
-pub trait SelectorPointer<T, Index> {
-    fn get_prt(&self) -> *const T;
-    fn get_mut_ptr(&mut self) -> *mut T;
+/// Returns a `Coproduct` of mutable references to the current vertex, or `None` if its slot is
+/// empty.
+pub trait GetMutsFromCoprod<'a, C> {
+    type Out: 'a;
+    fn get_muts(&'a mut self, c: &C) -> Option<Self::Out>;
 }
 
-impl<T, Rest> SelectorPointer<T, Here> for HCons<T, Rest> {
-    fn get_prt(&self) -> *const T {
+impl<'a> GetMutsFromCoprod<'a, CNil> for HNil {
+    type Out = CNil;
+
+    fn get_muts(&'a mut self, c: &CNil) -> Option<Self::Out> {
+        match *c {}
+    }
+}
+
+impl<'a, T, Policy, CRest, Rest> GetMutsFromCoprod<'a, Coproduct<PhantomData<T>, CRest>>
+    for HCons<VertexSlot<T, Policy>, Rest>
+where
+    T: 'a,
+    Policy: 'a,
+    Rest: GetMutsFromCoprod<'a, CRest>,
+{
+    type Out = Coproduct<&'a mut T, Rest::Out>;
+
+    fn get_muts(&'a mut self, c: &Coproduct<PhantomData<T>, CRest>) -> Option<Self::Out> {
+        match c {
+            Coproduct::Inl(_) => self.head.get_mut().map(Coproduct::Inl),
+            Coproduct::Inr(r) => self.tail.get_muts(r).map(Coproduct::Inr),
+        }
+    }
+}
+
+/// Returns the reference from a `Coproduct` of references as `dyn Any`.
+pub trait CoprodAsAny<'a> {
+    fn into_any(self) -> &'a dyn Any;
+}
+
+impl CoprodAsAny<'_> for CNil {
+    fn into_any(self) -> &'static dyn Any {
+        match self {}
+    }
+}
+
+impl<'a, T: 'static, Rest: CoprodAsAny<'a>> CoprodAsAny<'a> for Coproduct<&'a T, Rest> {
+    fn into_any(self) -> &'a dyn Any {
+        match self {
+            Coproduct::Inl(vertex) => vertex,
+            Coproduct::Inr(rest) => rest.into_any(),
+        }
+    }
+}
+
+/// Returns the mutable reference from a `Coproduct` of mutable references as `dyn Any`.
+pub trait CoprodAsAnyMut<'a> {
+    fn into_any_mut(self) -> &'a mut dyn Any;
+}
+
+impl<'a> CoprodAsAnyMut<'a> for CNil {
+    fn into_any_mut(self) -> &'a mut dyn Any {
+        match self {}
+    }
+}
+
+impl<'a, T: 'static, Rest: CoprodAsAnyMut<'a>> CoprodAsAnyMut<'a> for Coproduct<&'a mut T, Rest> {
+    fn into_any_mut(self) -> &'a mut dyn Any {
+        match self {
+            Coproduct::Inl(vertex) => vertex,
+            Coproduct::Inr(rest) => rest.into_any_mut(),
+        }
+    }
+}
+
+/// Selects the slot of vertex `V` in an `HList` of `VertexSlot`s.
+pub trait VertexSelector<V, Index> {
+    type Policy;
+
+    fn slot(&self) -> &VertexSlot<V, Self::Policy>;
+    fn slot_mut(&mut self) -> &mut VertexSlot<V, Self::Policy>;
+
+    /// Get the vertex if it exists.
+    fn vertex(&self) -> Option<&V>;
+
+    /// Get the vertex mutably if it exists.
+    fn vertex_mut(&mut self) -> Option<&mut V>;
+
+    /// Take the vertex out of the list, if it exists.
+    fn into_vertex(self) -> Option<V>;
+}
+
+impl<V, Policy, Rest> VertexSelector<V, Here> for HCons<VertexSlot<V, Policy>, Rest> {
+    type Policy = Policy;
+
+    fn slot(&self) -> &VertexSlot<V, Policy> {
         &self.head
     }
 
-    fn get_mut_ptr(&mut self) -> *mut T {
+    fn slot_mut(&mut self) -> &mut VertexSlot<V, Policy> {
         &mut self.head
     }
+
+    fn vertex(&self) -> Option<&V> {
+        self.head.get()
+    }
+
+    fn vertex_mut(&mut self) -> Option<&mut V> {
+        self.head.get_mut()
+    }
+
+    fn into_vertex(self) -> Option<V> {
+        self.head.into_inner()
+    }
+}
+
+impl<U, V, Rest, Idx> VertexSelector<V, There<Idx>> for HCons<U, Rest>
+where
+    Rest: VertexSelector<V, Idx>,
+{
+    type Policy = Rest::Policy;
+
+    fn slot(&self) -> &VertexSlot<V, Self::Policy> {
+        self.tail.slot()
+    }
+
+    fn slot_mut(&mut self) -> &mut VertexSlot<V, Self::Policy> {
+        self.tail.slot_mut()
+    }
+
+    fn vertex(&self) -> Option<&V> {
+        self.tail.vertex()
+    }
+
+    fn vertex_mut(&mut self) -> Option<&mut V> {
+        self.tail.vertex_mut()
+    }
+
+    fn into_vertex(self) -> Option<V> {
+        self.tail.into_vertex()
+    }
+}
+
+/// Selects mutable references to slots of two different vertices at the same time.
+///
+/// There is no implementation for `A == B`, so it is impossible to get two mutable references to
+/// one slot.
+pub trait PairSelector<A, B, Indices> {
+    type PolicyA;
+    type PolicyB;
+
+    fn get_pair_mut(
+        &mut self,
+    ) -> (
+        &mut VertexSlot<A, Self::PolicyA>,
+        &mut VertexSlot<B, Self::PolicyB>,
+    );
+}
+
+impl<A, B, PA, Rest, Idx> PairSelector<A, B, (Here, There<Idx>)> for HCons<VertexSlot<A, PA>, Rest>
+where
+    Rest: VertexSelector<B, Idx>,
+{
+    type PolicyA = PA;
+    type PolicyB = Rest::Policy;
+
+    fn get_pair_mut(&mut self) -> (&mut VertexSlot<A, PA>, &mut VertexSlot<B, Rest::Policy>) {
+        (&mut self.head, self.tail.slot_mut())
+    }
+}
+
+impl<A, B, PB, Rest, Idx> PairSelector<A, B, (There<Idx>, Here)> for HCons<VertexSlot<B, PB>, Rest>
+where
+    Rest: VertexSelector<A, Idx>,
+{
+    type PolicyA = Rest::Policy;
+    type PolicyB = PB;
+
+    fn get_pair_mut(&mut self) -> (&mut VertexSlot<A, Rest::Policy>, &mut VertexSlot<B, PB>) {
+        (self.tail.slot_mut(), &mut self.head)
+    }
 }
 
-impl<U, T, Rest, Idx> SelectorPointer<T, There<Idx>> for HCons<U, Rest>
+impl<U, A, B, Rest, IdxA, IdxB> PairSelector<A, B, (There<IdxA>, There<IdxB>)> for HCons<U, Rest>
 where
-    Rest: SelectorPointer<T, Idx>,
+    Rest: PairSelector<A, B, (IdxA, IdxB)>,
 {
-    fn get_prt(&self) -> *const T {
-        self.tail.get_prt()
+    type PolicyA = Rest::PolicyA;
+    type PolicyB = Rest::PolicyB;
+
+    fn get_pair_mut(
+        &mut self,
+    ) -> (
+        &mut VertexSlot<A, Self::PolicyA>,
+        &mut VertexSlot<B, Self::PolicyB>,
+    ) {
+        self.tail.get_pair_mut()
     }
+}
 
-    fn get_mut_ptr(&mut self) -> *mut T {
-        self.tail.get_mut_ptr()
+/// A type-level list of types, such as `Hlist![A, B]`.
+pub trait TypeList {
+    /// Add type names of the list to `names`.
+    fn names(names: &mut Vec<&'static str>);
+}
+
+impl TypeList for HNil {
+    fn names(_: &mut Vec<&'static str>) {}
+}
+
+impl<H: 'static, Rest: TypeList> TypeList for HCons<H, Rest> {
+    fn names(names: &mut Vec<&'static str>) {
+        names.push(std::any::type_name::<H>());
+        Rest::names(names);
+    }
+}
+
+/// Comparison of two indices of the vertex list, which is resolved at compile time.
+pub trait SameIndex<Other> {
+    const SAME: bool;
+}
+
+impl SameIndex<Here> for Here {
+    const SAME: bool = true;
+}
+
+impl<Idx> SameIndex<There<Idx>> for Here {
+    const SAME: bool = false;
+}
+
+impl<Idx> SameIndex<Here> for There<Idx> {
+    const SAME: bool = false;
+}
+
+impl<IdxA, IdxB> SameIndex<There<IdxB>> for There<IdxA>
+where
+    IdxA: SameIndex<IdxB>,
+{
+    const SAME: bool = IdxA::SAME;
+}
+
+/// A list of vertex types, such as `Hlist![A, B]`, where `Indices` are the indices of the
+/// vertices in `Vertexes`. Every type of the list must be a vertex of the state machine.
+pub trait VertexIndices<Vertexes, Indices> {}
+
+impl<Vertexes> VertexIndices<Vertexes, HNil> for HNil {}
+
+impl<H, Rest, Vertexes, Idx, IdxRest> VertexIndices<Vertexes, HCons<Idx, IdxRest>>
+    for HCons<H, Rest>
+where
+    Vertexes: VertexSelector<H, Idx>,
+    Rest: VertexIndices<Vertexes, IdxRest>,
+{
+}
+
+/// A list of indices of the vertex list, that may contain `Idx`. It is resolved at compile time.
+pub trait ContainsIndex<Idx> {
+    const CONTAINS: bool;
+}
+
+impl<Idx> ContainsIndex<Idx> for HNil {
+    const CONTAINS: bool = false;
+}
+
+impl<H, Rest, Idx> ContainsIndex<Idx> for HCons<H, Rest>
+where
+    H: SameIndex<Idx>,
+    Rest: ContainsIndex<Idx>,
+{
+    const CONTAINS: bool = H::SAME || Rest::CONTAINS;
+}
+
+/// A path of vertices from the vertex of the state machine to the vertex of its innermost sub
+/// state machine, like `(Checkout, Payment, AwaitingCard)`. A `StateMachineVertex` is written as
+/// its `IDX`. See `StateMachine::is_in`.
+pub trait StatePath {
+    /// The path as an `HList`, like `Hlist![Checkout, Payment, AwaitingCard]`.
+    type Path;
+}
+
+macro_rules! impl_state_path {
+    ($($vertex:ident),+) => {
+        impl<$($vertex),+> StatePath for ($($vertex,)+) {
+            type Path = Hlist![$($vertex),+];
+        }
+    };
+}
+
+impl_state_path!(A);
+impl_state_path!(A, B);
+impl_state_path!(A, B, C);
+impl_state_path!(A, B, C, D);
+
+/// The index of a `StateMachineVertex` selected by its `IDX` by `PathVertex` and
+/// `SubMachineSelector`, instead of `Here`.
+pub struct SubHere;
+
+/// The current vertex `Coproduct`, that may be in the vertex `T` of a `StatePath`: the vertex `T`
+/// itself at `Here`, or the `StateMachineVertex` whose `IDX` is `T` at `SubHere`.
+pub trait PathVertex<T, Idx> {
+    fn is_current(&self) -> bool;
+}
+
+impl<T, Rest> PathVertex<T, Here> for Coproduct<PhantomData<T>, Rest> {
+    fn is_current(&self) -> bool {
+        matches!(self, Coproduct::Inl(_))
+    }
+}
+
+impl<IDX, SM, Entry, Exit, Rest> PathVertex<IDX, SubHere>
+    for Coproduct<PhantomData<StateMachineVertex<IDX, SM, Entry, Exit>>, Rest>
+{
+    fn is_current(&self) -> bool {
+        matches!(self, Coproduct::Inl(_))
+    }
+}
+
+impl<T, V, Rest, Idx> PathVertex<T, There<Idx>> for Coproduct<V, Rest>
+where
+    Rest: PathVertex<T, Idx>,
+{
+    fn is_current(&self) -> bool {
+        match self {
+            Coproduct::Inl(_) => false,
+            Coproduct::Inr(rest) => rest.is_current(),
+        }
+    }
+}
+
+/// Selects the sub state machine of the `StateMachineVertex` whose `IDX` is `T` in the vertex list.
+pub trait SubMachineSelector<T, Idx> {
+    type Sm;
+
+    /// The sub state machine, or `None` if its slot is empty.
+    fn sub_machine(&self) -> Option<&Self::Sm>;
+}
+
+impl<IDX, SM, Entry, Exit, Policy, Rest> SubMachineSelector<IDX, SubHere>
+    for HCons<VertexSlot<StateMachineVertex<IDX, SM, Entry, Exit>, Policy>, Rest>
+{
+    type Sm = SM;
+
+    fn sub_machine(&self) -> Option<&SM> {
+        self.head.get().map(StateMachineVertex::sm)
+    }
+}
+
+impl<T, V, Rest, Idx> SubMachineSelector<T, There<Idx>> for HCons<V, Rest>
+where
+    Rest: SubMachineSelector<T, Idx>,
+{
+    type Sm = Rest::Sm;
+
+    fn sub_machine(&self) -> Option<&Self::Sm> {
+        self.tail.sub_machine()
+    }
+}
+
+/// A state machine that may be in the `Path` of `StatePath`. Every vertex of the path except the
+/// last one must be a `StateMachineVertex`, and the next vertex must be a vertex of its sub state
+/// machine, otherwise the path does not compile.
+pub trait InStatePath<Path, Idx> {
+    fn is_in_path(&self) -> bool;
+}
+
+impl<C, State, Vertexes, VertHandlers, Transitions, FAllTransitions, Answer, GErr, T, Idx>
+    InStatePath<HCons<T, HNil>, Idx>
+    for StateMachine<C, State, Vertexes, VertHandlers, Transitions, FAllTransitions, Answer, GErr>
+where
+    C: PathVertex<T, Idx>,
+{
+    fn is_in_path(&self) -> bool {
+        self.current.is_current()
+    }
+}
+
+impl<
+        C,
+        State,
+        Vertexes,
+        VertHandlers,
+        Transitions,
+        FAllTransitions,
+        Answer,
+        GErr,
+        T,
+        Next,
+        Rest,
+        Idx,
+        SubIdx,
+    > InStatePath<HCons<T, HCons<Next, Rest>>, (Idx, SubIdx)>
+    for StateMachine<C, State, Vertexes, VertHandlers, Transitions, FAllTransitions, Answer, GErr>
+where
+    C: PathVertex<T, Idx>,
+    Vertexes: SubMachineSelector<T, Idx>,
+    Vertexes::Sm: InStatePath<HCons<Next, Rest>, SubIdx>,
+{
+    fn is_in_path(&self) -> bool {
+        self.current.is_current()
+            && self
+                .vertexes
+                .sub_machine()
+                .is_some_and(InStatePath::is_in_path)
     }
 }
@@ -1,83 +1,141 @@
-use crate::process_result::ProcessResultSubstate;
-use crate::vertex::{StateMachineVertex, TerminationPseudoState};
-use crate::{CurrentStateIs, ProcessEvent};
-use frunk::coproduct::{CNil, CoproductSelector};
+use crate::process_event::ProcessEventKind;
+use crate::process_result::{ProcessError, ProcessResultSubstate, TransitionInfo, TransitionKind};
+use crate::trigger::EventKind;
+use crate::utils::IsTerminated;
+use crate::vertex::{StateMachineVertex, VertexSlot};
+use crate::StateMachine;
+use frunk::coproduct::CNil;
 use frunk::{Coproduct, HCons, HNil};
 use std::marker::PhantomData;
 
-pub trait VertexHandler<Vertex, Idx, Event, Answer, GErr, Other> {
-    fn process(
+pub trait VertexHandler<Vertex, Idx, Event: EventKind, Answer, GErr, Other> {
+    fn process<'e>(
         &mut self,
         vertex: &mut Vertex,
         idx: &Idx,
-        event: &Event,
+        event: &'e Event::Of<'e>,
     ) -> ProcessResultSubstate<Answer, GErr>;
+
+    /// Check the event like `process`, without changing the vertex. Returns `None` if the state
+    /// machine must look for a transition from the vertex.
+    fn probe<'e>(
+        &self,
+        vertex: &Vertex,
+        idx: &Idx,
+        event: &'e Event::Of<'e>,
+    ) -> Option<Result<TransitionInfo, ProcessError<GErr>>>;
 }
 
+#[derive(Clone)]
 pub struct EmptyVertexHandler;
-impl<Vertex, Event, Answer, GErr> VertexHandler<Vertex, (), Event, Answer, GErr, ()>
+impl<Vertex, Event: EventKind, Answer, GErr> VertexHandler<Vertex, (), Event, Answer, GErr, ()>
     for EmptyVertexHandler
 {
-    fn process(
+    fn process<'e>(
         &mut self,
         _: &mut Vertex,
         _: &(),
-        _: &Event,
+        _: &'e Event::Of<'e>,
     ) -> ProcessResultSubstate<Answer, GErr> {
         ProcessResultSubstate::MustLeaveState
     }
+
+    fn probe<'e>(
+        &self,
+        _: &Vertex,
+        _: &(),
+        _: &'e Event::Of<'e>,
+    ) -> Option<Result<TransitionInfo, ProcessError<GErr>>> {
+        None
+    }
 }
 
+#[derive(Clone)]
 pub struct SubStateMachineVertexHandler;
-impl<C, IDX, SM, Entry, Exit, Event, Answer, GErr, Idx, Other>
+impl<IDX, SM, Entry, Exit, Policy, Event: EventKind, Answer, GErr, Other>
     VertexHandler<
-        StateMachineVertex<IDX, SM, Entry, Exit>,
+        VertexSlot<StateMachineVertex<IDX, SM, Entry, Exit>, Policy>,
         (),
         Event,
         Answer,
         GErr,
-        (Idx, Other, C),
+        Other,
     > for SubStateMachineVertexHandler
 where
-    C: CoproductSelector<PhantomData<TerminationPseudoState>, Idx>,
-    SM: CurrentStateIs<Idx, C> + ProcessEvent<Event, Answer, GErr, Other>,
+    SM: IsTerminated + ProcessEventKind<Event, Answer, GErr, Other>,
 {
-    fn process(
+    fn process<'e>(
         &mut self,
-        sub: &mut StateMachineVertex<IDX, SM, Entry, Exit>,
+        sub: &mut VertexSlot<StateMachineVertex<IDX, SM, Entry, Exit>, Policy>,
         _: &(),
-        event: &Event,
+        event: &'e Event::Of<'e>,
     ) -> ProcessResultSubstate<Answer, GErr> {
-        if sub.sm.is::<TerminationPseudoState>() {
+        let Some(sub) = sub.get_mut() else {
+            return ProcessResultSubstate::MustLeaveState;
+        };
+        if sub.sm.is_terminated() {
             ProcessResultSubstate::MustLeaveState
         } else {
             use crate::process_result::ProcessResult::*;
 
-            match sub.sm.process(event) {
+            match sub.sm.process_kind(event) {
                 Handled(answer) => ProcessResultSubstate::Handled(answer),
+                Terminated => ProcessResultSubstate::MustLeaveState,
+                Ignored => ProcessResultSubstate::Ignored,
+                Deferred => ProcessResultSubstate::Deferred,
                 NoTransitions => ProcessResultSubstate::NoTransitions,
                 GuardErr(g) => ProcessResultSubstate::GuardErr(g),
+                Conflict => ProcessResultSubstate::Conflict,
+                OwnedEventRequired => ProcessResultSubstate::OwnedEventRequired,
             }
         }
     }
+
+    fn probe<'e>(
+        &self,
+        sub: &VertexSlot<StateMachineVertex<IDX, SM, Entry, Exit>, Policy>,
+        _: &(),
+        event: &'e Event::Of<'e>,
+    ) -> Option<Result<TransitionInfo, ProcessError<GErr>>> {
+        match sub.get()?.sm.can_process_kind(event) {
+            Err(ProcessError::Terminated) => None,
+            Ok(info) => Some(Ok(TransitionInfo {
+                kind: TransitionKind::SubMachine,
+                ..info
+            })),
+            Err(e) => Some(Err(e)),
+        }
+    }
 }
 
-impl<Vertex, Event, Answer, GErr> VertexHandler<Vertex, CNil, Event, Answer, GErr, ()> for HNil {
-    fn process(
+impl<Vertex, Event: EventKind, Answer, GErr> VertexHandler<Vertex, CNil, Event, Answer, GErr, ()>
+    for HNil
+{
+    fn process<'e>(
         &mut self,
         _: &mut Vertex,
         idx: &CNil,
-        _: &Event,
+        _: &'e Event::Of<'e>,
     ) -> ProcessResultSubstate<Answer, GErr> {
         match *idx {}
     }
+
+    fn probe<'e>(
+        &self,
+        _: &Vertex,
+        idx: &CNil,
+        _: &'e Event::Of<'e>,
+    ) -> Option<Result<TransitionInfo, ProcessError<GErr>>> {
+        match *idx {}
+    }
 }
 
 impl<
         Vertex,
+        Policy,
         IdxRest,
         Vertices,
-        Event,
+        Event: EventKind,
         Answer,
         GErr,
         VertHandler,
@@ -86,7 +144,7 @@ impl<
         OtherRest,
     >
     VertexHandler<
-        HCons<Vertex, Vertices>,
+        HCons<VertexSlot<Vertex, Policy>, Vertices>,
         Coproduct<PhantomData<Vertex>, IdxRest>,
         Event,
         Answer,
@@ -94,18 +152,193 @@ impl<
         (Other, OtherRest),
     > for HCons<VertHandler, VertHandlers>
 where
-    VertHandler: VertexHandler<Vertex, (), Event, Answer, GErr, Other>,
+    VertHandler: VertexHandler<VertexSlot<Vertex, Policy>, (), Event, Answer, GErr, Other>,
     VertHandlers: VertexHandler<Vertices, IdxRest, Event, Answer, GErr, OtherRest>,
 {
-    fn process(
+    fn process<'e>(
         &mut self,
-        vertices: &mut HCons<Vertex, Vertices>,
+        vertices: &mut HCons<VertexSlot<Vertex, Policy>, Vertices>,
         idx: &Coproduct<PhantomData<Vertex>, IdxRest>,
-        event: &Event,
+        event: &'e Event::Of<'e>,
     ) -> ProcessResultSubstate<Answer, GErr> {
         match idx {
             Coproduct::Inl(_) => self.head.process(&mut vertices.head, &(), event),
             Coproduct::Inr(right) => self.tail.process(&mut vertices.tail, right, event),
         }
     }
+
+    fn probe<'e>(
+        &self,
+        vertices: &HCons<VertexSlot<Vertex, Policy>, Vertices>,
+        idx: &Coproduct<PhantomData<Vertex>, IdxRest>,
+        event: &'e Event::Of<'e>,
+    ) -> Option<Result<TransitionInfo, ProcessError<GErr>>> {
+        match idx {
+            Coproduct::Inl(_) => self.head.probe(&vertices.head, &(), event),
+            Coproduct::Inr(right) => self.tail.probe(&vertices.tail, right, event),
+        }
+    }
+}
+
+/// Names the current vertex and collects the active configuration of sub state machines, see
+/// `StateMachine::current_name` and `StateMachine::active_configuration`.
+///
+/// A vertex is named by its type name, a `StateMachineVertex` is named by the type name of `IDX`.
+pub trait ActiveConfiguration<Vertex, Idx> {
+    fn vertex_name(&self, vertex: &Vertex, idx: &Idx) -> &'static str;
+
+    fn active_configuration(&self, vertex: &Vertex, idx: &Idx, out: &mut Vec<&'static str>);
+}
+
+impl<Vertex, Policy> ActiveConfiguration<VertexSlot<Vertex, Policy>, ()> for EmptyVertexHandler {
+    fn vertex_name(&self, _: &VertexSlot<Vertex, Policy>, _: &()) -> &'static str {
+        std::any::type_name::<Vertex>()
+    }
+
+    fn active_configuration(
+        &self,
+        vertex: &VertexSlot<Vertex, Policy>,
+        idx: &(),
+        out: &mut Vec<&'static str>,
+    ) {
+        out.push(self.vertex_name(vertex, idx));
+    }
+}
+
+impl<
+        IDX,
+        C,
+        State,
+        Vertexes,
+        VertHandlers,
+        Transitions,
+        FAllTrans,
+        Answer,
+        GErr,
+        Entry,
+        Exit,
+        Policy,
+    >
+    ActiveConfiguration<
+        VertexSlot<
+            StateMachineVertex<
+                IDX,
+                StateMachine<
+                    C,
+                    State,
+                    Vertexes,
+                    VertHandlers,
+                    Transitions,
+                    FAllTrans,
+                    Answer,
+                    GErr,
+                >,
+                Entry,
+                Exit,
+            >,
+            Policy,
+        >,
+        (),
+    > for SubStateMachineVertexHandler
+where
+    VertHandlers: ActiveConfiguration<Vertexes, C>,
+{
+    fn vertex_name(
+        &self,
+        _: &VertexSlot<
+            StateMachineVertex<
+                IDX,
+                StateMachine<
+                    C,
+                    State,
+                    Vertexes,
+                    VertHandlers,
+                    Transitions,
+                    FAllTrans,
+                    Answer,
+                    GErr,
+                >,
+                Entry,
+                Exit,
+            >,
+            Policy,
+        >,
+        _: &(),
+    ) -> &'static str {
+        std::any::type_name::<IDX>()
+    }
+
+    fn active_configuration(
+        &self,
+        sub: &VertexSlot<
+            StateMachineVertex<
+                IDX,
+                StateMachine<
+                    C,
+                    State,
+                    Vertexes,
+                    VertHandlers,
+                    Transitions,
+                    FAllTrans,
+                    Answer,
+                    GErr,
+                >,
+                Entry,
+                Exit,
+            >,
+            Policy,
+        >,
+        idx: &(),
+        out: &mut Vec<&'static str>,
+    ) {
+        out.push(self.vertex_name(sub, idx));
+        if let Some(sub) = sub.get() {
+            let sm = &sub.sm;
+            sm.vertices_handlers
+                .active_configuration(&sm.vertexes, &sm.current, out);
+        }
+    }
+}
+
+impl ActiveConfiguration<HNil, CNil> for HNil {
+    fn vertex_name(&self, _: &HNil, idx: &CNil) -> &'static str {
+        match *idx {}
+    }
+
+    fn active_configuration(&self, _: &HNil, idx: &CNil, _: &mut Vec<&'static str>) {
+        match *idx {}
+    }
+}
+
+impl<Vertex, Policy, IdxRest, Vertices, VertHandler, VertHandlers>
+    ActiveConfiguration<
+        HCons<VertexSlot<Vertex, Policy>, Vertices>,
+        Coproduct<PhantomData<Vertex>, IdxRest>,
+    > for HCons<VertHandler, VertHandlers>
+where
+    VertHandler: ActiveConfiguration<VertexSlot<Vertex, Policy>, ()>,
+    VertHandlers: ActiveConfiguration<Vertices, IdxRest>,
+{
+    fn vertex_name(
+        &self,
+        vertices: &HCons<VertexSlot<Vertex, Policy>, Vertices>,
+        idx: &Coproduct<PhantomData<Vertex>, IdxRest>,
+    ) -> &'static str {
+        match idx {
+            Coproduct::Inl(_) => self.head.vertex_name(&vertices.head, &()),
+            Coproduct::Inr(right) => self.tail.vertex_name(&vertices.tail, right),
+        }
+    }
+
+    fn active_configuration(
+        &self,
+        vertices: &HCons<VertexSlot<Vertex, Policy>, Vertices>,
+        idx: &Coproduct<PhantomData<Vertex>, IdxRest>,
+        out: &mut Vec<&'static str>,
+    ) {
+        match idx {
+            Coproduct::Inl(_) => self.head.active_configuration(&vertices.head, &(), out),
+            Coproduct::Inr(right) => self.tail.active_configuration(&vertices.tail, right, out),
+        }
+    }
 }
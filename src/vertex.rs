@@ -1,4 +1,14 @@
 //! Vertex interfaces and instances.
+//!
+//! Each vertex is stored in a `VertexSlot`, whose policy decides what happens to the vertex when
+//! it is entered and left. In the vertex list of `state_machine!` it is declared as:
+//! - `vertex` or `retain(vertex)` - the vertex keeps its data between activations.
+//! - `reset: Default(Vertex)` - the vertex is reset to `Vertex::default()` on entry.
+//! - `reset: Clone(vertex)` - the vertex is reset to a clone of `vertex` on entry.
+//! - `factory => Vertex` - the vertex is constructed by `factory` on entry and dropped on exit.
+//!
+//! A sub state machine can be declared with a policy too, e.g. `@Sub reset: Clone(sub)`, so it
+//! is started again from its initial state each time it is entered.
 
 use crate::StateMachine;
 use std::marker::PhantomData;
@@ -7,6 +17,10 @@ use std::marker::PhantomData;
 ///
 /// Called after `Action` and `ExitVertex` for `Target` vertex.
 pub trait EntryVertex {
+    /// `true` for final vertices. `StateMachine` is terminated when it enters a final vertex, see
+    /// `FinalVertex`.
+    const FINAL: bool = false;
+
     fn entry(&mut self) {}
 }
 
@@ -17,6 +31,184 @@ pub trait ExitVertex {
     fn exit(&mut self) {}
 }
 
+/// A final vertex: `Final<Outcome>` or `TerminationPseudoState`. It sets `EntryVertex::FINAL`
+/// and does not implement `ExitVertex`. When `StateMachine` enters it, further events return
+/// `ProcessResult::Terminated`, a sub state machine gives control back to the outer one, and
+/// `StateMachine::finish` can take the state machine apart.
+pub trait FinalVertex: EntryVertex {}
+
+/// Storage of a vertex in `StateMachine`.
+///
+/// `Policy` decides when the vertex is constructed and dropped:
+/// - `Retain` - the vertex is constructed with `StateMachine` and lives as long as it.
+/// - `OnEntry` - the vertex is constructed by a factory from the context and the event when it is
+///   entered, and dropped on exit.
+/// - `ResetDefault` - the vertex is reset to `Default::default()` when it is entered.
+/// - `ResetClone` - the vertex is reset to a clone of its initial value when it is entered.
+#[derive(Clone)]
+pub struct VertexSlot<V, Policy> {
+    vertex: Option<V>,
+    policy: Policy,
+}
+
+impl<V> VertexSlot<V, Retain> {
+    pub fn new(vertex: V) -> Self {
+        VertexSlot {
+            vertex: Some(vertex),
+            policy: Retain,
+        }
+    }
+}
+
+impl<V, F> VertexSlot<V, OnEntry<F>> {
+    pub fn on_entry(factory: F) -> Self {
+        VertexSlot {
+            vertex: None,
+            policy: OnEntry(factory),
+        }
+    }
+}
+
+impl<V: Default> VertexSlot<V, ResetDefault> {
+    pub fn reset_default() -> Self {
+        VertexSlot {
+            vertex: Some(V::default()),
+            policy: ResetDefault,
+        }
+    }
+}
+
+impl<V: Clone> VertexSlot<V, ResetClone<V>> {
+    pub fn reset_clone(initial: V) -> Self {
+        VertexSlot {
+            vertex: Some(initial.clone()),
+            policy: ResetClone(initial),
+        }
+    }
+}
+
+impl<V, Policy> VertexSlot<V, Policy> {
+    /// Get the vertex if it exists.
+    pub fn get(&self) -> Option<&V> {
+        self.vertex.as_ref()
+    }
+
+    /// Get the vertex mutably if it exists.
+    pub fn get_mut(&mut self) -> Option<&mut V> {
+        self.vertex.as_mut()
+    }
+
+    /// Take the vertex out of the slot, if it exists.
+    pub fn into_inner(self) -> Option<V> {
+        self.vertex
+    }
+
+    /// Take the vertex out of the slot and leave it empty.
+    pub(crate) fn take(&mut self) -> Option<V> {
+        self.vertex.take()
+    }
+
+    /// Construct the vertex, if it is required by `Policy`, before it is entered. Returns `None`
+    /// if the vertex still does not exist.
+    pub(crate) fn enter<Ctx, Event: ?Sized>(
+        &mut self,
+        ctx: &mut Ctx,
+        event: &Event,
+    ) -> Option<&mut V>
+    where
+        Policy: EntryPolicy<V, Ctx, Event>,
+    {
+        self.policy.enter(&mut self.vertex, ctx, event);
+        self.vertex.as_mut()
+    }
+
+    /// Call `ExitVertex` and drop the vertex, if it is required by `Policy`.
+    pub(crate) fn leave(&mut self)
+    where
+        V: ExitVertex,
+        Policy: ExitPolicy<V>,
+    {
+        if let Some(vertex) = self.vertex.as_mut() {
+            vertex.exit();
+        }
+        self.policy.leave(&mut self.vertex);
+    }
+}
+
+/// A policy of a vertex that is constructed with `StateMachine` and lives as long as it. The
+/// vertex keeps its data between activations.
+#[derive(Clone)]
+pub struct Retain;
+
+/// A policy of a vertex that is reset to `Default::default()` every time it is entered.
+#[derive(Clone)]
+pub struct ResetDefault;
+
+/// A policy of a vertex that is reset to a clone of the initial value every time it is entered.
+/// For `StateMachineVertex` it restarts the sub state machine from its initial state.
+#[derive(Clone)]
+pub struct ResetClone<V>(pub V);
+
+/// A policy of a vertex that is constructed by the factory `F` when it is entered and dropped on
+/// exit. `F` is `Fn(&mut Ctx, &Event) -> Vertex` for events of all transitions to the vertex.
+///
+/// A loop does not construct the vertex again.
+#[derive(Clone)]
+pub struct OnEntry<F>(pub F);
+
+/// What happens to a vertex when a transition enters it.
+pub trait EntryPolicy<V, Ctx, Event: ?Sized> {
+    fn enter(&self, vertex: &mut Option<V>, ctx: &mut Ctx, event: &Event);
+}
+
+/// What happens to a vertex when a transition leaves it.
+pub trait ExitPolicy<V> {
+    fn leave(&self, vertex: &mut Option<V>);
+}
+
+impl<V, Ctx, Event: ?Sized> EntryPolicy<V, Ctx, Event> for Retain {
+    fn enter(&self, _: &mut Option<V>, _: &mut Ctx, _: &Event) {}
+}
+
+impl<V> ExitPolicy<V> for Retain {
+    fn leave(&self, _: &mut Option<V>) {}
+}
+
+impl<V: Default, Ctx, Event: ?Sized> EntryPolicy<V, Ctx, Event> for ResetDefault {
+    fn enter(&self, vertex: &mut Option<V>, _: &mut Ctx, _: &Event) {
+        *vertex = Some(V::default());
+    }
+}
+
+impl<V> ExitPolicy<V> for ResetDefault {
+    fn leave(&self, _: &mut Option<V>) {}
+}
+
+impl<V: Clone, Ctx, Event: ?Sized> EntryPolicy<V, Ctx, Event> for ResetClone<V> {
+    fn enter(&self, vertex: &mut Option<V>, _: &mut Ctx, _: &Event) {
+        *vertex = Some(self.0.clone());
+    }
+}
+
+impl<V> ExitPolicy<V> for ResetClone<V> {
+    fn leave(&self, _: &mut Option<V>) {}
+}
+
+impl<V, Ctx, Event: ?Sized, F> EntryPolicy<V, Ctx, Event> for OnEntry<F>
+where
+    F: Fn(&mut Ctx, &Event) -> V,
+{
+    fn enter(&self, vertex: &mut Option<V>, ctx: &mut Ctx, event: &Event) {
+        *vertex = Some((self.0)(ctx, event));
+    }
+}
+
+impl<V, F> ExitPolicy<V> for OnEntry<F> {
+    fn leave(&self, vertex: &mut Option<V>) {
+        *vertex = None;
+    }
+}
+
 /// Action that do nothing.
 pub struct EmptyVertex<T>(PhantomData<T>);
 impl<T> EmptyVertex<T> {
@@ -25,6 +217,18 @@ impl<T> EmptyVertex<T> {
     }
 }
 
+impl<T> Clone for EmptyVertex<T> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Default for EmptyVertex<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> EntryVertex for EmptyVertex<T> {}
 impl<T> ExitVertex for EmptyVertex<T> {}
 
@@ -32,19 +236,44 @@ impl<T> ExitVertex for EmptyVertex<T> {}
 /// transition in this state.
 ///
 /// https://www.uml-diagrams.org/state-machine-diagrams.html#initial-pseudostate
+#[derive(Clone)]
 pub struct InitialPseudoState;
 impl ExitVertex for InitialPseudoState {
     fn exit(&mut self) {}
 }
 
+/// A final vertex that carries the typed outcome of `StateMachine`. Like `TerminationPseudoState`
+/// it does not implement `ExitVertex`, so the state machine cannot leave it.
+///
+/// The outcome is usually constructed on entry by a factory, e.g. `summarize => Final<Summary>`
+/// in `state_machine!`, and taken by `StateMachine::finish`.
+pub struct Final<Outcome>(pub Outcome);
+impl<Outcome> EntryVertex for Final<Outcome> {
+    const FINAL: bool = true;
+}
+impl<Outcome> FinalVertex for Final<Outcome> {}
+
+/// The event that moves `StateMachine` from `InitialPseudoState` by the initial transition. In
+/// `state_machine!` the initial transition is written as `initial => Target | action`, and it is
+/// run by `StateMachine::start`.
+pub struct Start;
+
+impl crate::Request for Start {
+    type Answer = ();
+}
+
 /// PseudoState from which `StateMachine` cannot leave because it not implement `ExitVertex`.
 /// `StateMachineVertex` must transition in this state to leave substate.
 ///
 /// https://www.uml-diagrams.org/state-machine-diagrams.html#terminate-pseudostate
+#[derive(Clone)]
 pub struct TerminationPseudoState;
 impl EntryVertex for TerminationPseudoState {
+    const FINAL: bool = true;
+
     fn entry(&mut self) {}
 }
+impl FinalVertex for TerminationPseudoState {}
 
 /// Sub state machine or composite state. Used local transitions.
 ///
@@ -57,6 +286,30 @@ pub struct StateMachineVertex<IDX, SM, Entry, Exit> {
     pub(crate) phantom: PhantomData<IDX>,
 }
 
+impl<IDX, SM, Entry, Exit> StateMachineVertex<IDX, SM, Entry, Exit> {
+    /// The nested state machine.
+    pub fn sm(&self) -> &SM {
+        &self.sm
+    }
+
+    /// The nested state machine, which can be changed without processing events by the outer
+    /// state machine.
+    pub fn sm_mut(&mut self) -> &mut SM {
+        &mut self.sm
+    }
+}
+
+impl<IDX, SM: Clone, Entry: Clone, Exit: Clone> Clone for StateMachineVertex<IDX, SM, Entry, Exit> {
+    fn clone(&self) -> Self {
+        StateMachineVertex {
+            sm: self.sm.clone(),
+            entry: self.entry.clone(),
+            exit: self.exit.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
 impl<
         IDX,
         Current,